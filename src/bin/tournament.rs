@@ -0,0 +1,8 @@
+use everpuzzle::scripts;
+
+/// plays seeded cpu matches without a window, "tournament MATCHES AI:DIFFICULTY..."
+/// lives in its own binary so it runs without the window / gl libraries the game links
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    scripts::run_tournament(&args);
+}
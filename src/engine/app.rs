@@ -3,6 +3,94 @@ use crate::helpers::*;
 use crate::scripts::*;
 use gilrs::Button;
 use miniquad::*;
+use std::panic::AssertUnwindSafe;
+
+/// keys that keep their frame times while paused, all other inputs wait for the next stepped frame
+const PAUSE_KEYS: [KeyCode; 5] = [
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::P,
+    KeyCode::N,
+    KeyCode::B,
+];
+/// buttons that keep their frame times while paused
+const PAUSE_BUTTONS: [Button; 1] = [Button::Select];
+/// amount of frames B has to be held till rewinding repeats each frame
+const REWIND_REPEAT: u32 = 25;
+
+/// what a frame does while the game is paused
+#[derive(Debug, PartialEq)]
+enum PausedFrame {
+    /// nothing advances
+    Wait,
+
+    /// a snapshot of the timeline is shown instead
+    Restored,
+
+    /// the game advances a single frame
+    Step,
+}
+
+/// B rewinds through the timeline, N moves forward through it before stepping a single frame again
+fn paused_frame(
+    input: &Input,
+    timeline: &mut Timeline,
+    grids: &mut Vec<Grid>,
+    garbage_system: &mut GarbageSystem,
+    mode: &mut Mode,
+) -> PausedFrame {
+    let rewind = input
+        .key_down_frames(KeyCode::B)
+        .filter(|&frames| frames == 1 || frames > REWIND_REPEAT)
+        .is_some();
+    let step = input.key_pressed(KeyCode::N);
+
+    let snapshot = if rewind {
+        timeline.back()
+    } else if step {
+        timeline.forward()
+    } else {
+        None
+    };
+
+    match snapshot {
+        Some(snapshot) => {
+            *grids = snapshot.grids.clone();
+            *garbage_system = snapshot.garbage_system.clone();
+
+            if let Some(progress) = snapshot.progress {
+                mode.set_progress(progress);
+            }
+
+            PausedFrame::Restored
+        }
+
+        None if step => PausedFrame::Step,
+        None => PausedFrame::Wait,
+    }
+}
+
+/// advances the grids of the mode a single frame
+fn update_mode(
+    mode: &mut Mode,
+    input: &Input,
+    grids: &mut [Grid],
+    garbage_system: &mut GarbageSystem,
+    pointer: &mut Pointer,
+) {
+    // mouse / touch swaps happen before the grids update, like the cursor input
+    if mode.playing() {
+        pointer.update(input, grids, mode.offsets());
+    }
+
+    match mode {
+        Mode::Versus(..) => update_versus(input, grids, garbage_system),
+        Mode::StageClear(stage) => stage.update(input, &mut grids[0], garbage_system),
+        Mode::LineClear(line_clear) => line_clear.update(input, &mut grids[0], garbage_system),
+        Mode::Sandbox(sandbox) => sandbox.update(input, grids, garbage_system, &VERSUS_OFFSETS),
+        Mode::Editor(editor) => editor.update(input, &mut grids[0], garbage_system, V2::zero()),
+    }
+}
 
 /// state of the Application, includes drawing, input, generators
 pub struct App {
@@ -20,6 +108,27 @@ pub struct App {
 
     /// debug info turned of by default
     debug: bool,
+
+    /// mode the grids are played in
+    mode: Mode,
+
+    /// stops all updates till unpaused or a single frame is stepped
+    paused: bool,
+
+    /// snapshots of the last frames that can be scrubbed through while paused
+    timeline: Timeline,
+
+    /// open rebinding screen, the game waits till it is closed
+    rebind: Option<RebindScreen>,
+
+    /// player whose gamepad disconnected, the game stays paused till it is unpaused
+    disconnected: Option<usize>,
+
+    /// mouse / touch drag to swap control of the first grid
+    pointer: Pointer,
+
+    /// id of the touch that drives the mouse, other fingers are ignored
+    touch: Option<u64>,
 }
 
 impl App {
@@ -30,12 +139,116 @@ impl App {
             Grid::gen_field(&mut temp_random, 5)
         };
 
+        let mut mode = Mode::from_args(std::env::args().skip(1));
+        let mut garbage_system = GarbageSystem::default();
+
+        // second versus grid is played by the cpu, falls back to the heuristic cpu if a bot fails to start
+        let versus_grids = |settings: &VersusSettings| {
+            let mut grids = vec![Grid::new(0, 1, &vframes), Grid::new(1, 2, &vframes)];
+            grids[1].cursor.player = 1;
+
+            if settings.players < 2 {
+                let ai = interactive_ai_from_name(&settings.cpu)
+                    .unwrap_or_else(|| Box::new(HeuristicAi::default()));
+                grids[1].set_ai(Some(ai));
+                grids[1].cursor.difficulty = settings.difficulty;
+            }
+
+            grids
+        };
+
+        let grids = match &mut mode {
+            Mode::Sandbox(_) => versus_grids(&VersusSettings::default()),
+
+            Mode::Versus(settings) => {
+                let mut grids = versus_grids(settings);
+
+                if let Some(field) = &settings.field {
+                    for grid in grids.iter_mut() {
+                        field.apply(grid, &mut garbage_system);
+                    }
+                }
+
+                grids
+            }
+
+            Mode::StageClear(stage) => {
+                let mut grid = Grid::new(0, 1, &vframes);
+                stage.start(&mut grid, &mut garbage_system);
+                vec![grid]
+            }
+
+            Mode::LineClear(line_clear) => {
+                let mut grid = Grid::new(0, 1, &vframes);
+                line_clear.start(&mut grid, &mut garbage_system);
+                vec![grid]
+            }
+
+            Mode::Editor(editor) => {
+                let mut grid = Grid::empty();
+
+                if std::path::Path::new(&editor.path).exists() {
+                    editor.load(&mut grid, &mut garbage_system);
+                }
+
+                vec![grid]
+            }
+        };
+
+        let mut input = Input::default();
+        input.controls = Controls::load(CONTROLS_PATH);
+
+        // both players on one keyboard, unless the second player has keys of their own
+        if let Mode::Versus(settings) = &mode {
+            if settings.players >= 2 && !input.controls.player(1).has_keys() {
+                input.share_keyboard();
+            }
+        }
+
         Self {
-            input: Input::default(),
+            input,
             sprites: Sprites::new(ctx),
-            grids: vec![Grid::new(0, 1, &vframes), Grid::new(1, 2, &vframes)],
-            garbage_system: GarbageSystem::default(),
+            grids,
+            garbage_system,
             debug: false,
+            mode,
+            paused: false,
+            timeline: Timeline::default(),
+            rebind: None,
+            disconnected: None,
+            pointer: Pointer::default(),
+            touch: None,
+        }
+    }
+}
+
+impl App {
+    /// joined gamepads take over their versus grid from the cpu, disconnects pause the game
+    fn update_players(&mut self) {
+        if !self.input.joined.is_empty() {
+            self.apply_players();
+        }
+
+        if let Some(&player) = self.input.disconnected.first() {
+            eprintln!("APP: paused, gamepad of player {} disconnected", player + 1);
+            self.disconnected = Some(player);
+            self.paused = true;
+        }
+    }
+
+    /// grids of joined players are played by them instead of the cpu
+    /// also applied after rewinding, since snapshots may be older than the join
+    fn apply_players(&mut self) {
+        if matches!(self.mode, Mode::Versus(..) | Mode::Sandbox(_)) {
+            for &player in self.input.gamepad_players.values() {
+                if let Some(grid) = self.grids.get_mut(player) {
+                    if grid.cursor.ai {
+                        grid.set_ai(None);
+                    }
+
+                    grid.cursor.player = player;
+                }
+            }
         }
     }
 }
@@ -44,89 +257,121 @@ impl EventHandler for App {
     /// updates the game based on the windows hz
     fn update(&mut self, ctx: &mut Context) {
         self.input.update_gamepad();
+        self.update_players();
+
+        // typed text goes to the name entry, single key shortcuts wait till it is closed
+        let typing = matches!(&self.mode, Mode::Editor(editor) if editor.naming());
 
         // quit early
-        if self.input.key_pressed(KeyCode::Escape) {
+        if !typing && self.input.key_pressed(KeyCode::Escape) {
             ctx.quit();
         }
 
         // toggle debug info
-        if self.input.kb_pressed(KeyCode::Tab, Button::Select) {
+        if !typing && self.input.kb_pressed(KeyCode::Tab, Button::Select) {
             self.debug = !self.debug;
         }
 
-        // debug
-        if self.input.mouse.left_pressed {
-            let pos = I2::new(
-                ((self.input.mouse.position.x - 400.) / ATLAS_TILE).floor() as i32,
-                ((self.input.mouse.position.y + self.grids[1].push_amount) / ATLAS_TILE).floor()
-                    as i32,
-            );
+        // F1 opens the rebinding screen, closing it saves the controls
+        if !typing && self.input.key_pressed(KeyCode::F1) {
+            if self.rebind.take().is_some() {
+                if let Err(e) = self.input.controls.save(CONTROLS_PATH) {
+                    eprintln!("CONTROLS: failed to save {}: {}", CONTROLS_PATH, e);
+                }
 
-            self.grids[1]
-                .cursor
-                .states
-                .push_back(CursorState::MoveSwap {
-                    counter: 0,
-                    goal: pos,
-                });
+                if self.input.shares_keyboard() {
+                    self.input.share_keyboard();
+                }
+            } else {
+                self.rebind = Some(RebindScreen::default());
+            }
         }
 
-        if self.input.kb_pressed(KeyCode::A, Button::North) {
-            self.grids[1].gen_1d_garbage(&mut self.garbage_system, 6);
+        if let Some(rebind) = &mut self.rebind {
+            rebind.update(&mut self.input);
+            self.input.update_end();
+            return;
         }
 
-        if self.input.kb_pressed(KeyCode::Enter, Button::West) {
-            self.grids[1].gen_2d_garbage(&mut self.garbage_system, 2);
+        // each human player toggles their own hints
+        if !typing {
+            for grid in self.grids.iter_mut().filter(|grid| !grid.cursor.ai) {
+                if self
+                    .input
+                    .control_pressed(grid.cursor.player, Control::Hint)
+                {
+                    grid.hint.toggle();
+                }
+            }
         }
 
-        // reset grid
-        // TODO(Skytrias): garbage not resetting
-        if self.input.kb_pressed(KeyCode::Space, Button::Start) {
-            for grid in self.grids.iter_mut() {
-                grid.reset();
-            }
+        // mouse drag to swap, the editor keeps the mouse for itself
+        if !typing && self.input.key_pressed(KeyCode::M) {
+            self.pointer.toggle();
         }
 
-        // manual raise
-        if self.input.key_down(KeyCode::LeftShift)
-            || self.input.button_down(Button::LeftTrigger)
-            || self.input.button_down(Button::RightTrigger)
-        {
-            self.grids[0].push_raise = true;
+        // pause and step a single frame, held inputs only advance on stepped frames
+        if !typing && self.input.key_pressed(KeyCode::P) {
+            self.paused = !self.paused;
+            self.disconnected = None;
         }
 
-        // update all grids
-        let len = self.grids.len();
-        for i in 0..len {
-            self.grids[i].update(&self.input, &mut self.garbage_system);
+        if self.paused {
+            let frame = paused_frame(
+                &self.input,
+                &mut self.timeline,
+                &mut self.grids,
+                &mut self.garbage_system,
+                &mut self.mode,
+            );
 
-            // spawns garbage on other self.grids if a new combo arrives
-            for combo_index in 0..self.grids[i].combo_highlight.list.len() {
-                // TODO(Skytrias): creates copies, might be bad cuz of performance
-                if !self.grids[i].combo_highlight.list[combo_index].sent {
-                    let combo_data = self.grids[i].combo_highlight.list[combo_index];
+            if frame == PausedFrame::Restored {
+                self.apply_players();
+            }
 
-                    for j in 0..len {
-                        // skip on the same grid as the goal
-                        if i == j {
-                            continue;
-                        }
+            if frame != PausedFrame::Step {
+                self.input.update_end_only(&PAUSE_KEYS, &PAUSE_BUTTONS);
+                return;
+            }
+        }
 
-                        match combo_data.variant {
-                            ComboVariant::Combo => self.grids[j]
-                                .gen_1d_garbage(&mut self.garbage_system, combo_data.size as usize),
-                            ComboVariant::Chain => self.grids[j]
-                                .gen_2d_garbage(&mut self.garbage_system, combo_data.size as usize),
-                        }
-                    }
+        let mut update = || {
+            update_mode(
+                &mut self.mode,
+                &self.input,
+                &mut self.grids,
+                &mut self.garbage_system,
+                &mut self.pointer,
+            )
+        };
+
+        // debug builds pause instead of crashing, so the frames leading to the panic can be rewinded
+        let result = if cfg!(debug_assertions) {
+            std::panic::catch_unwind(AssertUnwindSafe(update))
+        } else {
+            update();
+            Ok(())
+        };
+
+        match result {
+            Ok(()) => self
+                .timeline
+                .push(&self.grids, &self.garbage_system, self.mode.progress()),
+
+            Err(_) => {
+                eprintln!("APP: paused after a panic, press B to rewind");
+
+                if let Some(snapshot) = self.timeline.current() {
+                    self.grids = snapshot.grids.clone();
+                    self.garbage_system = snapshot.garbage_system.clone();
 
-                    self.grids[i].combo_highlight.list[combo_index].sent = true;
+                    if let Some(progress) = snapshot.progress {
+                        self.mode.set_progress(progress);
+                    }
                 }
-            }
 
-            self.garbage_system.update(&mut self.grids[i]);
-            self.grids[i].push_update(&mut self.garbage_system);
+                self.paused = true;
+            }
         }
 
         self.input.update_end();
@@ -134,8 +379,85 @@ impl EventHandler for App {
 
     /// draws the entire app sprites each frame
     fn draw(&mut self, ctx: &mut Context) {
-        self.grids[0].draw(&mut self.sprites, v2(0., 0.), self.debug);
-        self.grids[1].draw(&mut self.sprites, v2(400., 0.), self.debug);
+        if let Some(rebind) = &self.rebind {
+            rebind.draw(&mut self.sprites, &self.input);
+            self.sprites.render(ctx);
+            ctx.commit_frame();
+            return;
+        }
+
+        match &self.mode {
+            Mode::Versus(..) => {
+                for (grid, &offset) in self.grids.iter_mut().zip(VERSUS_OFFSETS.iter()) {
+                    grid.draw(&mut self.sprites, offset, self.debug);
+
+                    // swaps left of a puzzle field
+                    if let Some(swaps) = grid.cursor.swaps_left {
+                        self.sprites.text(Text {
+                            content: &format!("swaps {}", swaps),
+                            position: offset + v2(0., (GRID_HEIGHT + 2) as f32 * ATLAS_TILE),
+                            scale: V2::broadcast(0.5),
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+
+            Mode::Sandbox(sandbox) => {
+                for (grid, &offset) in self.grids.iter_mut().zip(VERSUS_OFFSETS.iter()) {
+                    grid.draw(&mut self.sprites, offset, self.debug);
+                }
+
+                sandbox.draw(&mut self.sprites, &self.grids, &VERSUS_OFFSETS);
+            }
+
+            Mode::StageClear(stage) => {
+                self.grids[0].draw(&mut self.sprites, v2(0., 0.), self.debug);
+                stage.draw(&mut self.sprites, &self.grids[0], v2(0., 0.));
+            }
+
+            Mode::LineClear(line_clear) => {
+                self.grids[0].draw(&mut self.sprites, v2(0., 0.), self.debug);
+                line_clear.draw(&mut self.sprites, &self.grids[0], v2(0., 0.));
+            }
+
+            Mode::Editor(editor) => {
+                self.grids[0].draw(&mut self.sprites, v2(0., 0.), self.debug);
+                editor.draw(&mut self.sprites, &self.input, v2(0., 0.));
+            }
+        }
+
+        // gamepads that can still join a grid
+        if self.input.can_join() && matches!(self.mode, Mode::Versus(..) | Mode::Sandbox(_)) {
+            self.sprites.text(Text {
+                content: "press start to join",
+                position: v2(0., GRID_HEIGHT as f32 * ATLAS_TILE + ATLAS_TILE),
+                scale: V2::broadcast(0.5),
+                ..Default::default()
+            });
+        }
+
+        // show the frame data of every block while paused
+        if self.paused {
+            for (grid, &offset) in self.grids.iter().zip(VERSUS_OFFSETS.iter()) {
+                grid.draw_states(&mut self.sprites, offset);
+            }
+
+            let text = if let Some(player) = self.disconnected {
+                format!("paused player {} disconnected", player + 1)
+            } else if self.timeline.rewinded > 0 {
+                format!("paused rewind {}", self.timeline.rewinded)
+            } else {
+                "paused".to_string()
+            };
+
+            self.sprites.text(Text {
+                content: &text,
+                position: v2(0., GRID_HEIGHT as f32 * ATLAS_TILE),
+                scale: V2::broadcast(0.5),
+                ..Default::default()
+            });
+        }
 
         self.sprites.render(ctx);
         ctx.commit_frame();
@@ -146,6 +468,11 @@ impl EventHandler for App {
         self.input.down_event(keycode);
     }
 
+    /// updates the input internal typed text
+    fn char_event(&mut self, _: &mut Context, character: char, _: KeyMods, _: bool) {
+        self.input.char_event(character);
+    }
+
     /// updates the input internal keys up
     fn key_up_event(&mut self, _: &mut Context, keycode: KeyCode, _: KeyMods) {
         self.input.up_event(keycode);
@@ -157,42 +484,102 @@ impl EventHandler for App {
     }
 
     /// updates the input internal mouse down to true / release to false
-    fn mouse_button_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        button: MouseButton,
-        _x: f32,
-        _y: f32,
-    ) {
-        match button {
-            MouseButton::Left => {
-                self.input.mouse.left_down = true;
-                self.input.mouse.left_released = false;
-            }
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        self.input.mouse.position = v2(x, y);
 
-            MouseButton::Right => {
-                self.input.mouse.right_down = true;
-                self.input.mouse.left_released = false;
-            }
+        match button {
+            MouseButton::Left => self.input.mouse.left_down = true,
+            MouseButton::Right => self.input.mouse.right_down = true,
 
             _ => {}
         }
     }
 
     /// updates the input internal mouse down to false / release to true
-    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
-        match button {
-            MouseButton::Left => {
-                self.input.mouse.left_down = false;
-                self.input.mouse.left_released = true;
-            }
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        self.input.mouse.position = v2(x, y);
 
-            MouseButton::Right => {
-                self.input.mouse.right_down = false;
-                self.input.mouse.left_released = true;
-            }
+        match button {
+            MouseButton::Left => self.input.mouse.left_down = false,
+            MouseButton::Right => self.input.mouse.right_down = false,
 
             _ => {}
         }
     }
+
+    /// the first finger acts as the left mouse button, touching turns the pointer control on
+    fn touch_event(&mut self, _ctx: &mut Context, phase: TouchPhase, id: u64, x: f32, y: f32) {
+        if phase == TouchPhase::Started && self.touch.is_none() {
+            self.touch = Some(id);
+            self.pointer.enabled = true;
+            self.input.mouse.left_down = true;
+        }
+
+        if self.touch != Some(id) {
+            return;
+        }
+
+        self.input.mouse.position = v2(x, y);
+
+        if phase == TouchPhase::Ended || phase == TouchPhase::Cancelled {
+            self.touch = None;
+            self.input.mouse.left_down = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// hang counter of the block at the index
+    fn hang_counter(grids: &[Grid], index: usize) -> u32 {
+        match &grids[0][index] {
+            Component::Block {
+                state: BlockState::Hang { counter },
+                ..
+            } => *counter,
+            _ => panic!("no hanging block at {}", index),
+        }
+    }
+
+    /// while paused only N advances the grids, by a single frame per press
+    #[test]
+    fn paused_step() {
+        let mut input = Input::headless();
+        let mut timeline = Timeline::default();
+        let mut garbage_system = GarbageSystem::default();
+        let mut pointer = Pointer::default();
+        let mut mode = Mode::LineClear(LineClear::new(10, 120));
+        let mut grids = vec![Grid::empty()];
+        grids[0][0] = Component::Block {
+            block: Block::default(),
+            state: BlockState::Hang { counter: 0 },
+        };
+        timeline.push(&grids, &garbage_system, mode.progress());
+
+        let mut frame = |input: &mut Input, grids: &mut Vec<Grid>| {
+            let frame = paused_frame(input, &mut timeline, grids, &mut garbage_system, &mut mode);
+
+            if frame == PausedFrame::Step {
+                update_mode(&mut mode, input, grids, &mut garbage_system, &mut pointer);
+                input.update_end();
+            } else {
+                input.update_end_only(&PAUSE_KEYS, &PAUSE_BUTTONS);
+            }
+
+            frame
+        };
+
+        assert_eq!(frame(&mut input, &mut grids), PausedFrame::Wait);
+        assert_eq!(hang_counter(&grids, 0), 0);
+
+        input.down_event(KeyCode::N);
+        assert_eq!(frame(&mut input, &mut grids), PausedFrame::Step);
+        assert_eq!(hang_counter(&grids, 0), 1);
+
+        // holding N does not step again
+        assert_eq!(frame(&mut input, &mut grids), PausedFrame::Wait);
+        assert_eq!(hang_counter(&grids, 0), 1);
+    }
 }
@@ -0,0 +1,644 @@
+use gilrs::Button;
+use miniquad::KeyCode;
+use std::collections::HashMap;
+use std::fmt;
+
+/// file the controls are loaded from and saved to, relative to the working directory like data/
+pub const CONTROLS_PATH: &str = "controls.txt";
+/// players that can join and be rebound, one per versus grid
+pub const MAX_PLAYERS: usize = 2;
+/// stick deflection needed to hold a direction
+const DEFAULT_DEADZONE: f32 = 0.5;
+/// amount the stick can fall back below the deadzone before the direction releases
+const DEFAULT_HYSTERESIS: f32 = 0.15;
+/// frames a direction has to be held till the cursor starts repeating its movement
+const DEFAULT_DAS: u32 = 25;
+/// frames between repeated movements, 1 moves each frame
+const DEFAULT_ARR: u32 = 1;
+/// frames a swap that couldnt happen yet is retried
+const DEFAULT_SWAP_BUFFER: u32 = 8;
+
+/// keys of each player sharing a single keyboard, ordered like Control::ALL
+/// the left player plays on WASD, the right player on the arrows
+const SHARED_KEYS: [[KeyCode; 8]; 2] = [
+    [
+        KeyCode::W,
+        KeyCode::S,
+        KeyCode::A,
+        KeyCode::D,
+        KeyCode::Q,
+        KeyCode::E,
+        KeyCode::Key1,
+        KeyCode::R,
+    ],
+    [
+        KeyCode::Up,
+        KeyCode::Down,
+        KeyCode::Left,
+        KeyCode::Right,
+        KeyCode::Period,
+        KeyCode::Comma,
+        KeyCode::Enter,
+        KeyCode::Slash,
+    ],
+];
+
+/// every key that can be bound, used to parse key names
+const KEYS: [KeyCode; 118] = [
+    KeyCode::Space,
+    KeyCode::Apostrophe,
+    KeyCode::Comma,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Semicolon,
+    KeyCode::Equal,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::LeftBracket,
+    KeyCode::Backslash,
+    KeyCode::RightBracket,
+    KeyCode::GraveAccent,
+    KeyCode::Escape,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::CapsLock,
+    KeyCode::ScrollLock,
+    KeyCode::NumLock,
+    KeyCode::PrintScreen,
+    KeyCode::Pause,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+    KeyCode::F25,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpDecimal,
+    KeyCode::KpDivide,
+    KeyCode::KpMultiply,
+    KeyCode::KpSubtract,
+    KeyCode::KpAdd,
+    KeyCode::KpEnter,
+    KeyCode::KpEqual,
+    KeyCode::LeftShift,
+    KeyCode::LeftControl,
+    KeyCode::LeftAlt,
+    KeyCode::LeftSuper,
+    KeyCode::RightShift,
+    KeyCode::RightControl,
+    KeyCode::RightAlt,
+    KeyCode::RightSuper,
+    KeyCode::Menu,
+];
+
+/// every button that can be bound, used to parse button names
+const BUTTONS: [Button; 19] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+/// anything a player can do with their grid
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Control {
+    Up,
+    Down,
+    Left,
+    Right,
+    Swap,
+    Raise,
+
+    /// resets versus, confirms in stage / line clear
+    Start,
+
+    /// shows / hides the hints of the player
+    Hint,
+}
+
+impl Control {
+    /// all controls in the order they are saved and shown
+    pub const ALL: [Control; 8] = [
+        Control::Up,
+        Control::Down,
+        Control::Left,
+        Control::Right,
+        Control::Swap,
+        Control::Raise,
+        Control::Start,
+        Control::Hint,
+    ];
+
+    /// lowercase name used in the controls file
+    pub fn name(self) -> String {
+        format!("{:?}", self).to_lowercase()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Control::ALL
+            .iter()
+            .find(|control| control.name() == name)
+            .copied()
+    }
+}
+
+/// single key or gamepad button bound to a control
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(Button),
+}
+
+impl Binding {
+    /// parses "key:NAME" or "button:NAME", names are the same as in miniquad / gilrs
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.splitn(2, ':');
+
+        match (parts.next(), parts.next()) {
+            (Some("key"), Some(name)) => KEYS
+                .iter()
+                .find(|key| format!("{:?}", key) == name)
+                .map(|&key| Binding::Key(key)),
+
+            (Some("button"), Some(name)) => BUTTONS
+                .iter()
+                .find(|button| format!("{:?}", button) == name)
+                .map(|&button| Binding::Button(button)),
+
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "key:{:?}", key),
+            Binding::Button(button) => write!(f, "button:{:?}", button),
+        }
+    }
+}
+
+/// controls of a single player bound to any amount of keys and buttons
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    pub controls: HashMap<Control, Vec<Binding>>,
+
+    /// left stick and d-pad axis deflection from 0 to 1 a direction starts being held at
+    pub deadzone: f32,
+
+    /// held directions release once the deflection drops below deadzone - hysteresis
+    pub hysteresis: f32,
+
+    /// delayed auto shift, frames a direction is held till the cursor repeats the movement
+    pub das: u32,
+
+    /// auto repeat rate, frames between each repeated movement
+    pub arr: u32,
+
+    /// frames a swap pressed while the blocks cant swap waits for the first legal frame, 0 drops it
+    pub swap_buffer: u32,
+
+    /// smart swap, moving the cursor cancels the buffered swap instead of swapping at the new position
+    pub smart_swap: bool,
+}
+
+impl Default for Bindings {
+    /// arrows and S on the keyboard, d-pad and south / east on a gamepad
+    fn default() -> Self {
+        let defaults = [
+            (Control::Up, KeyCode::Up, vec![Button::DPadUp]),
+            (Control::Down, KeyCode::Down, vec![Button::DPadDown]),
+            (Control::Left, KeyCode::Left, vec![Button::DPadLeft]),
+            (Control::Right, KeyCode::Right, vec![Button::DPadRight]),
+            (Control::Swap, KeyCode::S, vec![Button::South, Button::East]),
+            (
+                Control::Raise,
+                KeyCode::LeftShift,
+                vec![Button::LeftTrigger, Button::RightTrigger],
+            ),
+            (Control::Start, KeyCode::Space, vec![Button::Start]),
+            (Control::Hint, KeyCode::H, vec![Button::North]),
+        ];
+
+        let controls = defaults
+            .iter()
+            .map(|(control, key, buttons)| {
+                let mut bindings = vec![Binding::Key(*key)];
+                bindings.extend(buttons.iter().map(|&button| Binding::Button(button)));
+                (*control, bindings)
+            })
+            .collect();
+
+        Self {
+            controls,
+            deadzone: DEFAULT_DEADZONE,
+            hysteresis: DEFAULT_HYSTERESIS,
+            das: DEFAULT_DAS,
+            arr: DEFAULT_ARR,
+            swap_buffer: DEFAULT_SWAP_BUFFER,
+            smart_swap: false,
+        }
+    }
+}
+
+impl Bindings {
+    /// default gamepad buttons without any keys, players past the first start with these
+    pub fn buttons() -> Self {
+        let mut bindings = Bindings::default();
+        bindings.remove_keys();
+        bindings
+    }
+
+    /// true if any control is bound to a key
+    pub fn has_keys(&self) -> bool {
+        self.controls
+            .values()
+            .flatten()
+            .any(|binding| matches!(binding, Binding::Key(_)))
+    }
+
+    /// removes all key bindings, buttons stay
+    pub fn remove_keys(&mut self) {
+        for bindings in self.controls.values_mut() {
+            bindings.retain(|binding| matches!(binding, Binding::Button(_)));
+        }
+    }
+
+    /// true if the axis deflection holds its direction, held directions release at a lower deflection
+    pub fn axis_held(&self, value: f32, held: bool) -> bool {
+        if held {
+            value > self.deadzone - self.hysteresis
+        } else {
+            value > self.deadzone
+        }
+    }
+
+    /// true if a direction held for the amount of frames moves the cursor
+    /// moves on the first frame, then each arr frames once held longer than das
+    pub fn repeats(&self, frames: u32) -> bool {
+        frames == 1 || (frames > self.das && (frames - self.das - 1) % self.arr.max(1) == 0)
+    }
+
+    /// bindings of the control, empty if nothing is bound
+    pub fn get(&self, control: Control) -> &[Binding] {
+        self.controls.get(&control).map_or(&[], |bindings| bindings)
+    }
+
+    /// replaces all bindings of the same kind, a key replaces the keys and a button the buttons
+    pub fn rebind(&mut self, control: Control, binding: Binding) {
+        let bindings = self.controls.entry(control).or_default();
+        bindings.retain(|other| std::mem::discriminant(other) != std::mem::discriminant(&binding));
+        bindings.push(binding);
+    }
+}
+
+/// bindings of all players, saved as text
+///
+/// "player N" starts the bindings of a player, followed by "CONTROL BINDING..." lines
+/// i.e. "swap key:S button:South", controls without a line stay unbound, # starts a comment
+/// "deadzone VALUE" and "hysteresis VALUE" set up the stick of the player, the hysteresis has to stay below the deadzone
+/// "das FRAMES" and "arr FRAMES" set up the auto repeat of the cursor movement
+/// "buffer FRAMES" and "smart on / off" set up the swap buffer
+#[derive(Clone, Debug, PartialEq)]
+pub struct Controls {
+    pub players: Vec<Bindings>,
+}
+
+impl Default for Controls {
+    /// the first player gets the keyboard, the others only gamepad buttons
+    fn default() -> Self {
+        let mut players = vec![Bindings::default()];
+        players.resize_with(MAX_PLAYERS, Bindings::buttons);
+        Self { players }
+    }
+}
+
+impl Controls {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut players: Vec<Bindings> = Vec::new();
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+            let mut words = line.split_whitespace();
+            let first = words.next().unwrap_or("");
+
+            if first == "player" {
+                players.push(Bindings {
+                    controls: HashMap::new(),
+                    ..Default::default()
+                });
+                continue;
+            }
+
+            let player = players
+                .last_mut()
+                .ok_or_else(|| error("bindings before the first player".to_string()))?;
+
+            if first == "das" || first == "arr" || first == "buffer" {
+                let frames = words
+                    .next()
+                    .and_then(|word| word.parse::<u32>().ok())
+                    .filter(|&frames| first != "arr" || frames > 0)
+                    .ok_or_else(|| error(format!("expected \"{} FRAMES\"", first)))?;
+
+                match first {
+                    "das" => player.das = frames,
+                    "arr" => player.arr = frames,
+                    _ => player.swap_buffer = frames,
+                }
+
+                continue;
+            }
+
+            if first == "smart" {
+                player.smart_swap = match words.next() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return Err(error("expected \"smart on\" or \"smart off\"".to_string())),
+                };
+
+                continue;
+            }
+
+            if first == "deadzone" || first == "hysteresis" {
+                let value = words
+                    .next()
+                    .and_then(|word| word.parse::<f32>().ok())
+                    .filter(|value| (0. ..1.).contains(value))
+                    .ok_or_else(|| error(format!("expected \"{} VALUE\" from 0 to 1", first)))?;
+
+                if first == "deadzone" {
+                    player.deadzone = value;
+                } else {
+                    player.hysteresis = value;
+                }
+
+                continue;
+            }
+
+            let control = Control::from_name(first)
+                .ok_or_else(|| error(format!("unknown control {:?}", first)))?;
+
+            let bindings = words
+                .map(|word| {
+                    Binding::parse(word).ok_or_else(|| error(format!("unknown binding {:?}", word)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            player.controls.insert(control, bindings);
+        }
+
+        if players.is_empty() {
+            return Err("no player found".to_string());
+        }
+
+        // held directions would never release otherwise
+        for (i, player) in players.iter().enumerate() {
+            if player.hysteresis >= player.deadzone {
+                return Err(format!(
+                    "player {}: hysteresis {} has to be below the deadzone {}",
+                    i + 1,
+                    player.hysteresis,
+                    player.deadzone
+                ));
+            }
+        }
+
+        // missing players only get the gamepad
+        if players.len() < MAX_PLAYERS {
+            players.resize_with(MAX_PLAYERS, Bindings::buttons);
+        }
+
+        Ok(Self { players })
+    }
+
+    /// loads the controls file, the default controls are used if it doesnt exist or is invalid
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Controls::parse(&content).unwrap_or_else(|e| {
+                eprintln!("CONTROLS: {} {}, using the default controls", path, e);
+                Controls::default()
+            }),
+
+            Err(_) => Controls::default(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_string()).map_err(|e| e.to_string())
+    }
+
+    /// splits the keyboard between the first two players, left WASD Q E, right arrows . ,
+    /// replaces only the keys, gamepad buttons stay
+    pub fn share_keyboard(&mut self) {
+        for (bindings, keys) in self.players.iter_mut().zip(SHARED_KEYS.iter()) {
+            bindings.remove_keys();
+
+            for (&control, &key) in Control::ALL.iter().zip(keys.iter()) {
+                bindings.rebind(control, Binding::Key(key));
+            }
+        }
+    }
+
+    /// bindings of the player, falls back to the last player
+    pub fn player(&self, player: usize) -> &Bindings {
+        &self.players[player.min(self.players.len() - 1)]
+    }
+}
+
+impl fmt::Display for Controls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, bindings) in self.players.iter().enumerate() {
+            writeln!(f, "player {}", i + 1)?;
+
+            for &control in Control::ALL.iter() {
+                write!(f, "{}", control.name())?;
+
+                for binding in bindings.get(control) {
+                    write!(f, " {}", binding)?;
+                }
+
+                writeln!(f)?;
+            }
+
+            writeln!(f, "deadzone {}", bindings.deadzone)?;
+            writeln!(f, "hysteresis {}", bindings.hysteresis)?;
+            writeln!(f, "das {}", bindings.das)?;
+            writeln!(f, "arr {}", bindings.arr)?;
+            writeln!(f, "buffer {}", bindings.swap_buffer)?;
+            writeln!(
+                f,
+                "smart {}",
+                if bindings.smart_swap { "on" } else { "off" }
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the cursor moves on the first frame, waits das frames, then moves every arr frames
+    #[test]
+    fn bindings_repeat() {
+        let mut bindings = Bindings::default();
+        let moves = |bindings: &Bindings| {
+            (1..=30)
+                .filter(|&frames| bindings.repeats(frames))
+                .collect::<Vec<u32>>()
+        };
+        assert_eq!(moves(&bindings), vec![1, 26, 27, 28, 29, 30]);
+
+        bindings.das = 10;
+        bindings.arr = 6;
+        assert_eq!(moves(&bindings), vec![1, 11, 17, 23, 29]);
+
+        let controls = Controls::parse("player 1\ndas 8\narr 2\n").unwrap();
+        assert_eq!((controls.players[0].das, controls.players[0].arr), (8, 2));
+    }
+
+    /// saved controls load the same, invalid lines are reported
+    #[test]
+    fn controls_round_trip() {
+        let mut controls = Controls::default();
+        controls.players[0].rebind(Control::Swap, Binding::Key(KeyCode::D));
+        assert_eq!(
+            controls.players[0].get(Control::Swap),
+            &[
+                Binding::Button(Button::South),
+                Binding::Button(Button::East),
+                Binding::Key(KeyCode::D),
+            ]
+        );
+
+        assert_eq!(Controls::parse(&controls.to_string()).unwrap(), controls);
+        assert_eq!(
+            Binding::parse("key:LeftShift"),
+            Some(Binding::Key(KeyCode::LeftShift))
+        );
+
+        // keys of the first player never drive the second
+        assert!(!controls.players[1].has_keys());
+        controls.share_keyboard();
+        assert_eq!(
+            controls.players[0].get(Control::Swap)[2],
+            Binding::Key(KeyCode::Q)
+        );
+        assert_eq!(
+            controls.players[1].get(Control::Left)[1],
+            Binding::Key(KeyCode::Left)
+        );
+        assert_eq!(
+            Controls::parse("player 1\n").unwrap().players.len(),
+            MAX_PLAYERS
+        );
+
+        assert!(Controls::parse("swap key:S\n").is_err());
+        assert!(Controls::parse("player 1\ndeadzone 2\n").is_err());
+        assert!(Controls::parse("player 1\ndeadzone 0.2\nhysteresis 0.2\n").is_err());
+        assert!(Controls::parse("player 1\nhysteresis 0.3\ndeadzone 0.4\n").is_ok());
+        assert!(Controls::parse("player 1\narr 0\n").is_err());
+        assert!(Controls::parse("player 1\nsmart maybe\n").is_err());
+        assert!(Controls::parse("player 1\njump key:S\n").is_err());
+        assert!(Controls::parse("player 1\nswap key:Nothing\n").is_err());
+    }
+}
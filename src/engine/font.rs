@@ -0,0 +1,209 @@
+use crate::helpers::*;
+use std::collections::HashMap;
+
+/// first vframe of the glyphs drawn from data/font.txt, below the hand drawn atlas
+pub const ATLAS_GLYPHS: u32 = 13;
+/// rows of glyphs drawn below the atlas, the shader expects ATLAS_GLYPHS + GLYPH_ROWS rows
+pub const GLYPH_ROWS: u32 = 3;
+/// tiles in a row of the atlas
+const ATLAS_COLUMNS: usize = 26;
+/// size of a single glyph pixel in the atlas
+const GLYPH_PIXEL: usize = 4;
+/// empty pixels left of each drawn glyph, close to the hand drawn ones
+const GLYPH_MARGIN: usize = 4;
+/// pixels between the right edge of a glyph and the next one
+const GLYPH_GAP: f32 = 3.;
+/// advance of whitespace
+const SPACE_ADVANCE: f32 = 16.;
+
+/// pixel rows of a parsed glyph, true where the glyph is drawn
+pub type GlyphRows = Vec<Vec<bool>>;
+
+/// where a character lives in the atlas and how far the next one is drawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Glyph {
+    pub hframe: u32,
+    pub vframe: u32,
+    pub advance: f32,
+}
+
+/// glyph table of the atlas, numbers and capitals are hand drawn, every other ascii glyph comes from data/font.txt
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl Font {
+    /// parses "glyph C" headers each followed by rows of X pixels and . empties
+    pub fn parse(content: &str) -> Result<Vec<(char, GlyphRows)>, String> {
+        let mut list: Vec<(char, GlyphRows)> = Vec::new();
+        let size = ATLAS_TILE as usize / GLYPH_PIXEL;
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: &str| format!("line {}: {}", number + 1, message);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(rest) = line.strip_prefix("glyph ") {
+                let c = rest
+                    .chars()
+                    .next()
+                    .ok_or_else(|| error("expected \"glyph C\""))?;
+                list.push((c, Vec::new()));
+                continue;
+            }
+
+            let (c, rows) = list
+                .last_mut()
+                .ok_or_else(|| error("row outside of a glyph"))?;
+
+            if rows.len() >= size || line.len() > size - GLYPH_MARGIN / GLYPH_PIXEL {
+                return Err(error(&format!("glyph {:?} is too big", c)));
+            }
+
+            let row = line
+                .chars()
+                .map(|c| match c {
+                    'X' => Ok(true),
+                    '.' => Ok(false),
+                    _ => Err(error(&format!("unknown pixel {:?}", c))),
+                })
+                .collect::<Result<Vec<bool>, String>>()?;
+            rows.push(row);
+        }
+
+        if list.len() > GLYPH_ROWS as usize * ATLAS_COLUMNS {
+            return Err(format!(
+                "more than {} glyphs",
+                GLYPH_ROWS as usize * ATLAS_COLUMNS
+            ));
+        }
+
+        Ok(list)
+    }
+
+    /// draws the glyphs of data/font.txt as new rows below the rgba atlas pixels
+    /// and measures the advance of every glyph by its rightmost pixel
+    pub fn new(pixels: &mut Vec<u8>, width: usize) -> Self {
+        let data = load_file!("data/font.txt");
+        let list = Font::parse(&String::from_utf8_lossy(&data)).unwrap_or_else(|e| {
+            eprintln!("FONT: data/font.txt {}", e);
+            Vec::new()
+        });
+
+        let tile = ATLAS_TILE as usize;
+        pixels.resize((ATLAS_GLYPHS + GLYPH_ROWS) as usize * tile * width * 4, 0);
+
+        let mut font = Self {
+            glyphs: HashMap::new(),
+        };
+
+        for (i, (c, rows)) in list.iter().enumerate() {
+            let (hframe, vframe) = (i % ATLAS_COLUMNS, ATLAS_GLYPHS as usize + i / ATLAS_COLUMNS);
+
+            for (y, row) in rows.iter().enumerate() {
+                for (x, _) in row.iter().enumerate().filter(|(_, &set)| set) {
+                    for py in 0..GLYPH_PIXEL {
+                        for px in 0..GLYPH_PIXEL {
+                            let pixel_x = hframe * tile + GLYPH_MARGIN + x * GLYPH_PIXEL + px;
+                            let pixel_y = vframe * tile + y * GLYPH_PIXEL + py;
+                            let index = (pixel_y * width + pixel_x) * 4;
+                            pixels[index..index + 4].copy_from_slice(&[0, 0, 0, 255]);
+                        }
+                    }
+                }
+            }
+
+            font.insert(*c, hframe as u32, vframe as u32, pixels, width);
+        }
+
+        for (i, c) in ('0'..='9').enumerate() {
+            font.insert(c, i as u32, ATLAS_NUMBERS, pixels, width);
+        }
+
+        for (i, c) in ('A'..='Z').enumerate() {
+            font.insert(c, i as u32, ATLAS_ALPHABET, pixels, width);
+        }
+
+        font
+    }
+
+    /// adds the glyph at the tile, the advance reaches past its rightmost visible pixel
+    fn insert(&mut self, c: char, hframe: u32, vframe: u32, pixels: &[u8], width: usize) {
+        let tile = ATLAS_TILE as usize;
+        let visible = |x: usize| {
+            (0..tile).any(|y| {
+                let pixel_x = hframe as usize * tile + x;
+                let pixel_y = vframe as usize * tile + y;
+                pixels
+                    .get((pixel_y * width + pixel_x) * 4 + 3)
+                    .map_or(false, |&alpha| alpha > 0)
+            })
+        };
+        let right = (0..tile).rev().find(|&x| visible(x)).map_or(0, |x| x + 1);
+
+        self.glyphs.insert(
+            c,
+            Glyph {
+                hframe,
+                vframe,
+                advance: right as f32 + GLYPH_GAP,
+            },
+        );
+    }
+
+    /// glyph of the character, ? for characters without one, none for whitespace
+    pub fn glyph(&self, c: char) -> Option<Glyph> {
+        if c.is_whitespace() {
+            return None;
+        }
+
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&'?'))
+            .copied()
+    }
+
+    /// unscaled distance to the next character
+    pub fn advance(&self, c: char) -> f32 {
+        self.glyph(c).map_or(SPACE_ADVANCE, |glyph| glyph.advance)
+    }
+
+    /// unscaled width of the whole text
+    pub fn width(&self, content: &str) -> f32 {
+        content.chars().map(|c| self.advance(c)).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// every printable ascii character has a glyph with its own advance, others fall back to ?
+    #[test]
+    fn font_glyphs() {
+        let width = ATLAS_COLUMNS * ATLAS_TILE as usize;
+        let mut pixels = vec![0; width * ATLAS_GLYPHS as usize * ATLAS_TILE as usize * 4];
+        let font = Font::new(&mut pixels, width);
+        assert_eq!(
+            pixels.len(),
+            width * (ATLAS_GLYPHS + GLYPH_ROWS) as usize * ATLAS_TILE as usize * 4
+        );
+
+        for c in '!'..='~' {
+            assert!(font.glyphs.contains_key(&c), "{:?} has no glyph", c);
+        }
+
+        assert_eq!(font.glyph('é'), font.glyph('?'));
+        assert_eq!(font.glyph(' '), None);
+        assert!(font.advance('i') < font.advance('m'));
+        assert_eq!(font.glyph('1').unwrap().vframe, ATLAS_NUMBERS);
+        assert_eq!(font.width("ab"), font.advance('a') + font.advance('b'));
+
+        assert!(Font::parse("X\n").is_err());
+        assert!(Font::parse("glyph a\nX?\n").is_err());
+        assert!(Font::parse("glyph a\nXXXXXXXX\n").is_err());
+    }
+}
@@ -1,7 +1,8 @@
+use crate::engine::{Binding, Bindings, Control, Controls, MAX_PLAYERS};
 use crate::helpers::*;
 use gilrs::{
-    ev::EventType::{ButtonPressed, ButtonReleased},
-    Button,
+    ev::EventType::{AxisChanged, ButtonPressed, ButtonReleased, Connected, Disconnected},
+    Axis, Button,
 };
 use miniquad::KeyCode;
 use std::collections::HashMap;
@@ -10,28 +11,53 @@ pub struct Input {
     /// data storage for each key that was pressed with the frame time
     key_downs: HashMap<KeyCode, u32>,
 
-    /// data storage for each button that was pressed with the frame time
-    button_downs: HashMap<Button, u32>,
+    /// data storage for each button that was pressed with the frame time, per gamepad id
+    button_downs: HashMap<(usize, Button), u32>,
 
-    gilrs: gilrs::Gilrs,
+    /// d-pad button each held stick direction stands for with the frame time, per gamepad id
+    stick_downs: HashMap<(usize, Button), u32>,
+
+    /// last left stick and d-pad axis values, per gamepad id
+    axes: HashMap<(usize, Axis), f32>,
+
+    /// connected gamepad ids
+    gamepads: Vec<usize>,
+
+    /// player index of each gamepad id that joined by pressing start
+    pub gamepad_players: HashMap<usize, usize>,
+
+    /// players that joined this frame
+    pub joined: Vec<usize>,
+
+    /// players whose gamepad disconnected this frame
+    pub disconnected: Vec<usize>,
+
+    /// gamepad context, none when running headless
+    gilrs: Option<gilrs::Gilrs>,
 
     /// mouse handle that which holds left / right button and position info
     pub mouse: Mouse,
+
+    /// characters typed this frame, control characters are left out
+    pub text: String,
+
+    /// bindings of each player, loaded from and saved to the controls file
+    pub controls: Controls,
+
+    /// controls with the keyboard split between the first two players, only used while running and never saved
+    shared: Option<Controls>,
 }
 
 impl Default for Input {
     fn default() -> Self {
-        Self {
-            key_downs: HashMap::new(),
-            button_downs: HashMap::new(),
-
+        let mut input = Self {
             // gamepad
             gilrs: match gilrs::GilrsBuilder::new().set_update_state(false).build() {
-                Ok(g) => g,
+                Ok(g) => Some(g),
                 Err(gilrs::Error::NotImplemented(g)) => {
                     eprintln!("Current platform is not supported");
 
-                    g
+                    Some(g)
                 }
                 Err(e) => {
                     eprintln!("Failed to create gilrs context: {}", e);
@@ -39,8 +65,20 @@ impl Default for Input {
                 }
             },
 
-            mouse: Mouse::default(),
+            ..Input::headless()
+        };
+
+        // gamepads that were connected before the start
+        let ids: Vec<usize> = match &input.gilrs {
+            Some(gilrs) => gilrs.gamepads().map(|(id, _)| id.into()).collect(),
+            None => Vec::new(),
+        };
+
+        for id in ids {
+            input.gamepad_connected(id);
         }
+
+        input
     }
 }
 
@@ -78,6 +116,25 @@ impl Default for Mouse {
 }
 
 impl Input {
+    /// input without any gamepad context, nothing is ever pressed, used for matches without a window
+    pub fn headless() -> Self {
+        Self {
+            key_downs: HashMap::new(),
+            button_downs: HashMap::new(),
+            stick_downs: HashMap::new(),
+            axes: HashMap::new(),
+            gamepads: Vec::new(),
+            gamepad_players: HashMap::new(),
+            joined: Vec::new(),
+            disconnected: Vec::new(),
+            gilrs: None,
+            mouse: Mouse::default(),
+            text: String::new(),
+            controls: Controls::default(),
+            shared: None,
+        }
+    }
+
     pub fn down_event(&mut self, keycode: KeyCode) {
         if let Some(value) = self.key_downs.get_mut(&keycode) {
             if *value == 0 {
@@ -88,6 +145,13 @@ impl Input {
         }
     }
 
+    /// appends a typed character to the text of the frame
+    pub fn char_event(&mut self, character: char) {
+        if !character.is_control() {
+            self.text.push(character);
+        }
+    }
+
     pub fn up_event(&mut self, keycode: KeyCode) {
         if let Some(value) = self.key_downs.get_mut(&keycode) {
             *value = 0;
@@ -109,25 +173,48 @@ impl Input {
         self.key_downs.get(&code).filter(|&&v| v == 1).is_some()
     }
 
-    /// returns true if a button is held down
+    /// returns true if a button is held down on any gamepad
     pub fn button_down(&self, button: Button) -> bool {
-        self.button_downs
-            .get(&button)
-            .filter(|&&v| v != 0)
-            .is_some()
+        self.button_down_frames(button).is_some()
     }
 
-    /// returns true the amount of frames a button has been down for
+    /// returns the amount of frames a button has been down for on any gamepad
     pub fn button_down_frames(&self, button: Button) -> Option<u32> {
-        self.button_downs.get(&button).filter(|&&v| v != 0).copied()
+        self.button_downs
+            .iter()
+            .chain(self.stick_downs.iter())
+            .filter(|&(&(_, b), &v)| b == button && v != 0)
+            .map(|(_, &v)| v)
+            .max()
     }
 
-    /// returns true if a button is pressed for a single frame
+    /// returns true if a button is pressed for a single frame on any gamepad
     pub fn button_pressed(&self, button: Button) -> bool {
         self.button_downs
-            .get(&button)
-            .filter(|&&v| v == 1)
-            .is_some()
+            .iter()
+            .chain(self.stick_downs.iter())
+            .any(|(&(_, b), &v)| b == button && v == 1)
+    }
+
+    /// returns the amount of frames a button has been down for on the gamepads the player joined with
+    pub fn player_button_frames(&self, player: usize, button: Button) -> Option<u32> {
+        self.button_downs
+            .iter()
+            .chain(self.stick_downs.iter())
+            .filter(|&(&(id, b), &v)| {
+                b == button && v != 0 && self.gamepad_players.get(&id) == Some(&player)
+            })
+            .map(|(_, &v)| v)
+            .max()
+    }
+
+    /// true if a connected gamepad did not join yet and a player is still free
+    pub fn can_join(&self) -> bool {
+        self.gamepad_players.len() < MAX_PLAYERS
+            && self
+                .gamepads
+                .iter()
+                .any(|id| !self.gamepad_players.contains_key(id))
     }
 
     /// returns true if a button or a key is held down
@@ -159,28 +246,206 @@ impl Input {
         self.key_pressed(code) || self.button_pressed(button)
     }
 
+    /// bindings the player currently plays with, the shared keyboard layout while it is on
+    pub fn bindings(&self, player: usize) -> &Bindings {
+        self.shared
+            .as_ref()
+            .unwrap_or(&self.controls)
+            .player(player)
+    }
+
+    /// splits the keyboard between the first two players till the game closes, the saved controls stay untouched
+    /// called again after rebinding, so changed buttons apply to the shared layout too
+    pub fn share_keyboard(&mut self) {
+        let mut shared = self.controls.clone();
+        shared.share_keyboard();
+        self.shared = Some(shared);
+    }
+
+    /// true if the keyboard is split between the first two players
+    pub fn shares_keyboard(&self) -> bool {
+        self.shared.is_some()
+    }
+
+    /// returns the amount of frames any binding of the players control has been down for
+    pub fn control_down_frames(&self, player: usize, control: Control) -> Option<u32> {
+        self.bindings(player)
+            .get(control)
+            .iter()
+            .filter_map(|binding| match *binding {
+                Binding::Key(key) => self.key_down_frames(key),
+                Binding::Button(button) => self.player_button_frames(player, button),
+            })
+            .max()
+    }
+
+    /// returns true if any binding of the players control is held down
+    pub fn control_down(&self, player: usize, control: Control) -> bool {
+        self.control_down_frames(player, control).is_some()
+    }
+
+    /// returns true if any binding of the players control is pressed for a single frame
+    pub fn control_pressed(&self, player: usize, control: Control) -> bool {
+        self.bindings(player)
+            .get(control)
+            .iter()
+            .any(|binding| match *binding {
+                Binding::Key(key) => self.key_pressed(key),
+                Binding::Button(button) => self.player_button_frames(player, button) == Some(1),
+            })
+    }
+
+    /// key or button that was pressed this frame, used for rebinding
+    pub fn pressed_binding(&self) -> Option<Binding> {
+        let key = self
+            .key_downs
+            .iter()
+            .find(|&(_, &frames)| frames == 1)
+            .map(|(&key, _)| Binding::Key(key));
+
+        key.or_else(|| {
+            self.button_downs
+                .iter()
+                .find(|&(_, &frames)| frames == 1)
+                .map(|(&(_, button), _)| Binding::Button(button))
+        })
+    }
+
     pub fn update_gamepad(&mut self) {
-        while let Some(gilrs::Event { event, .. }) = self.gilrs.next_event() {
+        // taken out while polling, so the events can update the rest of the input
+        let mut gilrs = match self.gilrs.take() {
+            Some(gilrs) => gilrs,
+            None => return,
+        };
+
+        while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+            let id: usize = id.into();
+
             match event {
-                ButtonPressed(btn, _) => {
-                    if let Some(value) = self.button_downs.get_mut(&btn) {
-                        if *value == 0 {
-                            *value = 1;
-                        }
-                    } else {
-                        self.button_downs.insert(btn, 1);
-                    }
-                }
+                ButtonPressed(btn, _) => self.gamepad_pressed(id, btn),
 
                 ButtonReleased(btn, _) => {
-                    if let Some(value) = self.button_downs.get_mut(&btn) {
+                    if let Some(value) = self.button_downs.get_mut(&(id, btn)) {
                         *value = 0;
                     }
                 }
 
+                AxisChanged(axis, value, _) => self.gamepad_axis(id, axis, value),
+                Connected => self.gamepad_connected(id),
+                Disconnected => self.gamepad_disconnected(id),
+
                 _ => {}
             }
         }
+
+        self.gilrs = Some(gilrs);
+    }
+
+    /// presses the button on the gamepad, start joins the first player without a gamepad
+    /// the joining press still counts as start of the new player
+    fn gamepad_pressed(&mut self, id: usize, button: Button) {
+        let value = self.button_downs.entry((id, button)).or_insert(0);
+        if *value == 0 {
+            *value = 1;
+        }
+
+        if button == Button::Start && !self.gamepad_players.contains_key(&id) {
+            let players = &self.gamepad_players;
+
+            if let Some(player) = (0..MAX_PLAYERS).find(|p| !players.values().any(|o| o == p)) {
+                self.join(id, player);
+            }
+        }
+    }
+
+    /// the gamepad controls the player from now on
+    fn join(&mut self, id: usize, player: usize) {
+        self.gamepad_players.insert(id, player);
+        self.joined.push(player);
+    }
+
+    /// moves an axis of the gamepad, the left stick and d-pad axes hold the d-pad directions
+    /// diagonals hold both directions, each with its own frame time
+    fn gamepad_axis(&mut self, id: usize, axis: Axis, value: f32) {
+        // up is positive on the y axes
+        let directions = match axis {
+            Axis::LeftStickX | Axis::DPadX => [(Button::DPadRight, 1.), (Button::DPadLeft, -1.)],
+            Axis::LeftStickY | Axis::DPadY => [(Button::DPadUp, 1.), (Button::DPadDown, -1.)],
+            _ => return,
+        };
+
+        self.axes.insert((id, axis), value);
+
+        // the stick and the d-pad axes of the same direction are combined
+        let (stick, dpad) = match axis {
+            Axis::LeftStickX | Axis::DPadX => (Axis::LeftStickX, Axis::DPadX),
+            _ => (Axis::LeftStickY, Axis::DPadY),
+        };
+        let axes = &self.axes;
+        let axis_value = |axis: Axis, sign: f32| axes.get(&(id, axis)).map_or(0., |&v| v * sign);
+
+        let player = self.gamepad_players.get(&id).copied().unwrap_or(0);
+        let bindings = self
+            .shared
+            .as_ref()
+            .unwrap_or(&self.controls)
+            .player(player);
+
+        for &(button, sign) in directions.iter() {
+            let frames = self.stick_downs.get(&(id, button)).copied().unwrap_or(0);
+            let value = axis_value(stick, sign).max(axis_value(dpad, sign));
+
+            if !bindings.axis_held(value, frames != 0) {
+                self.stick_downs.remove(&(id, button));
+            } else if frames == 0 {
+                self.stick_downs.insert((id, button), 1);
+            }
+        }
+    }
+
+    /// the first player doesnt have to press start, a pad joins them as soon as it connects
+    fn gamepad_connected(&mut self, id: usize) {
+        if !self.gamepads.contains(&id) {
+            self.gamepads.push(id);
+        }
+
+        if !self.gamepad_players.contains_key(&id)
+            && !self.gamepad_players.values().any(|&p| p == 0)
+        {
+            self.join(id, 0);
+        }
+    }
+
+    /// releases everything held on the gamepad, the player can join again with start
+    fn gamepad_disconnected(&mut self, id: usize) {
+        self.gamepads.retain(|&other| other != id);
+        self.button_downs.retain(|&(other, _), _| other != id);
+        self.stick_downs.retain(|&(other, _), _| other != id);
+        self.axes.retain(|&(other, _), _| other != id);
+
+        if let Some(player) = self.gamepad_players.remove(&id) {
+            self.disconnected.push(player);
+        }
+    }
+
+    /// increases the frame times of the listed keys / buttons only, used while the game is paused
+    /// so that held game inputs keep their frame time till the next stepped frame
+    pub fn update_end_only(&mut self, keys: &[KeyCode], buttons: &[Button]) {
+        for key in keys.iter() {
+            if let Some(value) = self.key_downs.get_mut(key) {
+                if *value != 0 {
+                    *value += 1;
+                }
+            }
+        }
+
+        for ((_, button), value) in self.button_downs.iter_mut() {
+            if *value != 0 && buttons.contains(button) {
+                *value += 1;
+            }
+        }
+
+        self.end_frame();
     }
 
     pub fn update_end(&mut self) {
@@ -198,17 +463,112 @@ impl Input {
             }
         }
 
+        for (_, value) in self.stick_downs.iter_mut() {
+            *value += 1;
+        }
+
+        self.end_frame();
+    }
+
+    /// clears the text / joins / disconnects of the frame and updates the mouse
+    fn end_frame(&mut self) {
+        self.text.clear();
+        self.joined.clear();
+        self.disconnected.clear();
+        self.update_mouse();
+    }
+
+    /// updates the mouse pressed / released states
+    fn update_mouse(&mut self) {
         self.mouse.left_pressed = if self.mouse.left_down {
             !self.mouse.last_left_down
         } else {
             false
         };
+        self.mouse.left_released = !self.mouse.left_down && self.mouse.last_left_down;
         self.mouse.last_left_down = self.mouse.left_down;
         self.mouse.right_pressed = if self.mouse.right_down {
             !self.mouse.last_right_down
         } else {
             false
         };
+        self.mouse.right_released = !self.mouse.right_down && self.mouse.last_right_down;
         self.mouse.last_right_down = self.mouse.right_down;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the first pad joins on connect, start joins the free players in order, buttons only control the joined player
+    #[test]
+    fn gamepad_join() {
+        let mut input = Input::headless();
+        input.gamepad_connected(7);
+        input.gamepad_connected(3);
+        assert_eq!(input.joined, vec![0]);
+        assert!(input.can_join());
+
+        input.gamepad_pressed(3, Button::Start);
+        assert_eq!(input.joined, vec![0, 1]);
+        assert!(input.control_pressed(1, Control::Start));
+        assert!(!input.control_pressed(0, Control::Start));
+        assert!(!input.can_join());
+
+        input.gamepad_pressed(3, Button::South);
+        assert!(input.control_pressed(1, Control::Swap));
+        assert!(!input.control_pressed(0, Control::Swap));
+
+        input.update_end();
+        assert!(input.joined.is_empty());
+
+        input.gamepad_disconnected(7);
+        assert_eq!(input.disconnected, vec![0]);
+        assert_eq!(input.gamepad_players.get(&3), Some(&1));
+
+        input.gamepad_connected(7);
+        assert_eq!(input.gamepad_players.get(&7), Some(&0));
+    }
+
+    /// the shared keyboard only changes the bindings in use, the saved controls keep the keys of the first player
+    #[test]
+    fn shared_keyboard() {
+        let mut input = Input::headless();
+        input.share_keyboard();
+        assert_eq!(input.controls, Controls::default());
+        assert!(!input.controls.to_string().contains("key:W"));
+
+        input.down_event(KeyCode::Up);
+        assert!(input.control_pressed(1, Control::Up));
+        assert!(!input.control_pressed(0, Control::Up));
+
+        input.down_event(KeyCode::W);
+        assert!(input.control_pressed(0, Control::Up));
+    }
+
+    /// stick directions count frames like buttons, diagonals hold both, hysteresis keeps them held
+    #[test]
+    fn gamepad_stick() {
+        let mut input = Input::headless();
+        input.gamepad_connected(1);
+        input.gamepad_pressed(1, Button::Start);
+
+        input.gamepad_axis(1, Axis::LeftStickX, 0.4);
+        assert!(!input.control_down(0, Control::Right));
+
+        input.gamepad_axis(1, Axis::LeftStickX, -0.7);
+        input.gamepad_axis(1, Axis::LeftStickY, 0.7);
+        assert!(input.control_pressed(0, Control::Left));
+        assert!(input.control_pressed(0, Control::Up));
+
+        input.update_end();
+        input.update_end();
+        input.gamepad_axis(1, Axis::LeftStickX, -0.4);
+        assert_eq!(input.control_down_frames(0, Control::Left), Some(3));
+
+        input.gamepad_axis(1, Axis::LeftStickX, -0.3);
+        assert!(!input.control_down(0, Control::Left));
+        assert!(input.control_down(0, Control::Up));
+    }
+}
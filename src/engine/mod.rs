@@ -4,5 +4,11 @@ pub use self::app::*;
 mod sprites;
 pub use self::sprites::*;
 
+mod font;
+pub use self::font::*;
+
 mod input;
 pub use self::input::*;
+
+mod bindings;
+pub use self::bindings::*;
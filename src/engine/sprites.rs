@@ -1,3 +1,4 @@
+use crate::engine::Font;
 use crate::helpers::*;
 use miniquad::*;
 
@@ -10,22 +11,27 @@ attribute vec2 i_tiles;
 attribute float i_hframe;
 attribute float i_vframe;
 attribute float i_depth;
+attribute vec4 i_tint;
 
 uniform mat4 projection;
 
 varying highp vec2 o_uv;
+varying lowp vec4 o_tint;
 
 void main() {
 	gl_Position = projection * i_model * vec4(v_pos, i_depth, 1.);
 	vec2 i_uv = v_pos * i_tiles;
 	o_uv.x = (i_hframe + i_uv.x) * (1. / 26.);
-    o_uv.y = (i_vframe + i_uv.y) * (1. / 13.);
+    // 13 rows of the atlas texture and the 3 rows of font glyphs
+    o_uv.y = (i_vframe + i_uv.y) * (1. / 16.);
+	o_tint = i_tint;
 }
 "#;
 
 /// fragment shader for the sprite rendering pipeline
 const FRAGMENT: &str = r#"#version 100
 varying highp vec2 o_uv;
+varying lowp vec4 o_tint;
 
 uniform sampler2D texture;
 
@@ -37,7 +43,8 @@ void main() {
         discard;
     }
 	
-	gl_FragColor = texture_color;
+    // black turns into the tint, white stays white
+	gl_FragColor = vec4(texture_color.rgb + o_tint.rgb * (1. - texture_color.rgb), texture_color.a * o_tint.a);
 }
 "#;
 
@@ -69,12 +76,15 @@ pub struct Sprites {
 
     /// data storage for all quads in the frame that you want to draw
     quads: Vec<Quad>,
+
+    /// glyph table of the atlas
+    font: Font,
 }
 
 impl Sprites {
     /// initializes the sprite rendering pipeline with defaults
     ///
-    /// also loads the texture atlas used across for all sprites
+    /// also loads the texture atlas used across for all sprites, the font glyphs are drawn below it
     pub fn new(ctx: &mut Context) -> Self {
         #[rustfmt::skip]
 			let vertices = [
@@ -94,7 +104,7 @@ impl Sprites {
 
         let instance_buffer = Buffer::stream(ctx, BufferType::VertexBuffer, Quad::MAX * Quad::SIZE);
 
-        let texture = {
+        let (texture, font) = {
             let data = load_file!("textures/atlas.png");
             let data = std::io::Cursor::new(data);
 
@@ -103,11 +113,15 @@ impl Sprites {
                 .last()
                 .expect("No frames in PNG")
                 .expect("PNG parsing error");
-            let width = raster.width();
-            let height = raster.height();
-            let texels = raster.as_u8_slice();
-
-            Texture::from_rgba8(ctx, width as u16, height as u16, &texels)
+            let width = raster.width() as usize;
+            let mut texels = raster.as_u8_slice().to_vec();
+            let font = Font::new(&mut texels, width);
+            let height = texels.len() / 4 / width;
+
+            (
+                Texture::from_rgba8(ctx, width as u16, height as u16, &texels),
+                font,
+            )
         };
         texture.set_filter(ctx, FilterMode::Nearest);
 
@@ -135,6 +149,7 @@ impl Sprites {
                 VertexAttribute::with_buffer("i_hframe", VertexFormat::Float1, 1),
                 VertexAttribute::with_buffer("i_vframe", VertexFormat::Float1, 1),
                 VertexAttribute::with_buffer("i_depth", VertexFormat::Float1, 1),
+                VertexAttribute::with_buffer("i_tint", VertexFormat::Float4, 1),
             ],
             shader,
             PipelineParams {
@@ -153,6 +168,7 @@ impl Sprites {
             quads: Vec::with_capacity(Quad::MAX),
             pipeline,
             bindings,
+            font,
         }
     }
 
@@ -163,44 +179,55 @@ impl Sprites {
         }
     }
 
-    /// pushes text transformed into multiple quads, look at the text data structure for info
-    ///
-    /// not efficient and doesn't look great
-    /// at some point i wan't to move to glyph_brush / rusttype or fontdue!
-    pub fn text(&mut self, text: Text) {
-        let len = text.content.len();
-
-        if self.quads.len() - len < Quad::MAX {
-            let mut position = text.position;
+    /// pushes a line as a quad rotated towards the end, the thickness is centered on the line
+    pub fn line(&mut self, line: Line) {
+        let direction = line.end - line.start;
+        let rotation = direction.y.atan2(direction.x);
+        let normal = v2(-rotation.sin(), rotation.cos());
+
+        self.push(Sprite {
+            position: line.start - normal * line.thickness / 2.,
+            scale: v2(direction.mag(), line.thickness) / ATLAS_TILE,
+            rotation,
+            hframe: line.hframe,
+            vframe: line.vframe,
+            depth: line.depth,
+            ..Default::default()
+        });
+    }
 
-            for c in text.content.chars() {
-                if c.is_whitespace() {
-                    position.x += text.step * text.scale.x;
-                    continue;
-                }
+    /// pushes a quad per glyph of the text, look at the text data structure for info
+    pub fn text(&mut self, text: Text) {
+        let mut position = text.position;
 
-                let (hframe, vframe) = {
-                    if c.is_digit(10) {
-                        (c.to_digit(10).unwrap(), ATLAS_NUMBERS)
-                    } else {
-                        (c.to_digit(35).unwrap() - 10, ATLAS_ALPHABET)
-                    }
-                };
+        match text.align {
+            Align::Left => {}
+            Align::Center => position.x -= self.text_width(text.content, text.scale) / 2.,
+            Align::Right => position.x -= self.text_width(text.content, text.scale),
+        }
 
+        for c in text.content.chars() {
+            if let Some(glyph) = self.font.glyph(c) {
                 self.push(Sprite {
                     position,
-                    hframe,
-                    vframe,
+                    hframe: glyph.hframe,
+                    vframe: glyph.vframe,
                     depth: 0.1,
                     scale: text.scale,
+                    tint: text.color,
                     ..Default::default()
                 });
-
-                position.x += text.step * text.scale.x;
             }
+
+            position.x += self.font.advance(c) * text.scale.x;
         }
     }
 
+    /// width of the text in pixels when drawn at the scale
+    pub fn text_width(&self, content: &str, scale: V2) -> f32 {
+        self.font.width(content) * scale.x
+    }
+
     /// calls gl render functions, draws all quads when called
     pub fn render(&mut self, ctx: &mut Context) {
         let (width, height) = ctx.screen_size();
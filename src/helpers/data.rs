@@ -38,6 +38,9 @@ pub const GRID_HEIGHT: usize = 12;
 /// block width * height size of the grid
 pub const GRID_TOTAL: usize = GRID_WIDTH * GRID_HEIGHT;
 
+/// pixel positions of the grids in versus
+pub const VERSUS_OFFSETS: [V2; 2] = [V2 { x: 0., y: 0. }, V2 { x: 400., y: 0. }];
+
 /// Sprite data used to render quads
 #[derive(Copy, Clone)]
 pub struct Sprite {
@@ -50,6 +53,9 @@ pub struct Sprite {
     pub centered: bool,
     pub tiles: V2,
     pub depth: f32,
+
+    /// black pixels of the texture are drawn in the tint, black leaves the texture as is
+    pub tint: V4,
 }
 
 /// default parameters that a sprite requires!
@@ -66,11 +72,12 @@ impl Default for Sprite {
             centered: false,
             tiles: V2::one(),
             depth: 0.9,
+            tint: v4(0., 0., 0., 1.),
         }
     }
 }
 
-/// data that will be sent to the gpu
+/// line between 2 pixel positions, drawn as a single rotated quad
 #[derive(Debug, Clone, Copy)]
 pub struct Line {
     pub start: V2,
@@ -78,6 +85,7 @@ pub struct Line {
     pub thickness: f32,
     pub hframe: u32,
     pub vframe: u32,
+    pub depth: f32,
 }
 
 impl Default for Line {
@@ -88,6 +96,7 @@ impl Default for Line {
             thickness: 10.,
             hframe: 0,
             vframe: ATLAS_FILL,
+            depth: 0.1,
         }
     }
 }
@@ -110,6 +119,9 @@ pub struct Quad {
 
     /// vframe of the tile in the texture atlas
     pub depth: f32,
+
+    /// color black texture pixels are drawn in, alpha multiplies the texture alpha
+    pub tint: V4,
 }
 
 impl Quad {
@@ -131,6 +143,11 @@ impl From<Sprite> for Quad {
             0.,
         ));
 
+        // rotates around the top left of the sprite, in radians
+        if sprite.rotation != 0. {
+            model = model * M4::from_rotation_z(sprite.rotation);
+        }
+
         model = model * M4::from_nonuniform_scale(v4(sprite.scale.x, sprite.scale.y, 1., 1.));
         model = model * M4::from_nonuniform_scale(v4(dimensions.x, dimensions.y, 1., 1.));
 
@@ -144,15 +161,26 @@ impl From<Sprite> for Quad {
             hframe: sprite.hframe as f32,
             vframe: sprite.vframe as f32,
             depth: sprite.depth,
+            tint: sprite.tint,
         }
     }
 }
 
+/// horizontal alignment of text to its position
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// text drawn with the glyphs of the atlas, any string is safe to draw
 pub struct Text<'a> {
     pub content: &'a str,
     pub position: V2,
     pub scale: V2,
-    pub step: f32,
+    pub align: Align,
+    pub color: V4,
 }
 
 impl<'a> Default for Text<'a> {
@@ -161,7 +189,8 @@ impl<'a> Default for Text<'a> {
             position: V2::zero(),
             scale: V2::one(),
             content: "",
-            step: 32.,
+            align: Align::Left,
+            color: v4(0., 0., 0., 1.),
         }
     }
 }
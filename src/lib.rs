@@ -0,0 +1,18 @@
+#![allow(unused_imports)]
+#![allow(dead_code)]
+#![allow(unused_variables)]
+
+/// loads a file at runtime in debug mode, includes the file into the binary in release mode
+macro_rules! load_file {
+    ($path:expr) => {
+        if cfg!(debug_assertions) {
+            std::fs::read($path).expect("Failed to open file")
+        } else {
+            include_bytes!(concat!("../../", $path)).to_vec()
+        }
+    };
+}
+
+pub mod engine;
+pub mod helpers;
+pub mod scripts;
@@ -1,26 +1,13 @@
-#![allow(unused_imports)]
-#![allow(dead_code)]
-#![allow(unused_variables)]
-
-/// loads a file at runtime in debug mode, includes the file into the binary in release mode
-macro_rules! load_file {
-    ($path:expr) => {
-        if cfg!(debug_assertions) {
-            std::fs::read($path).expect("Failed to open file")
-        } else {
-            include_bytes!(concat!("../../", $path)).to_vec()
-        }
-    };
-}
-
-mod engine;
-mod helpers;
-mod scripts;
-
+use everpuzzle::*;
 use miniquad::*;
 
-/// starts the entire game
+/// starts the entire game, "everpuzzle env ..." runs the training environment without a window instead
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some("env") = args.first().map(String::as_str) {
+        return scripts::run_env(&args[1..]);
+    }
+
     miniquad::start(conf::Conf::default(), |mut ctx| {
         UserData::owning(engine::App::new(&mut ctx), ctx)
     });
@@ -0,0 +1,93 @@
+use crate::scripts::{
+    CursorState, ExternalAi, GarbageSystem, Grid, HeuristicAi, Patterns, SearchAi,
+};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// what a controller wants the grid to do next
+#[derive(Default)]
+pub struct Plan {
+    /// cursor states that will be run in order
+    pub states: VecDeque<CursorState>,
+
+    /// raises the grid once
+    pub raise: bool,
+
+    /// how good the ai rated the plan, shown by the plan overlay
+    pub score: Option<i32>,
+}
+
+/// controller of a grid cursor, i.e. a cpu, a replay or a network opponent
+/// only gets a read-only view of the grid and is asked for a new plan once the cursor ran out of states
+pub trait Ai: Send {
+    /// plans the next cursor states for the grid
+    fn plan(&mut self, grid: &Grid, garbage_system: &GarbageSystem) -> Plan;
+
+    /// short name shown in the sandbox
+    fn name(&self) -> &'static str;
+
+    /// boxed copy, so grids holding an ai can still be cloned
+    fn clone_box(&self) -> Box<dyn Ai>;
+}
+
+impl Clone for Box<dyn Ai> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// creates the ai with the name, "heuristic", "search", "heuristic=PATH" with its own pattern file or "bot=COMMAND" for an external process
+pub fn ai_from_name(name: &str) -> Option<Box<dyn Ai>> {
+    match name {
+        "heuristic" => Some(Box::new(HeuristicAi::default())),
+
+        _ if name.starts_with("heuristic=") => match Patterns::load(&name[10..]) {
+            Ok(patterns) => Some(Box::new(HeuristicAi {
+                patterns: Arc::new(patterns),
+            })),
+
+            Err(e) => {
+                eprintln!("PATTERNS: failed to load {:?}: {}", &name[10..], e);
+                None
+            }
+        },
+        "search" => Some(Box::new(SearchAi::default())),
+
+        _ if name.starts_with("bot=") => spawn_bot(&name[4..], false),
+
+        _ => None,
+    }
+}
+
+/// wether ai_from_name knows the name, bot commands are only checked for being non empty so no process is started
+pub fn is_ai_name(name: &str) -> bool {
+    match name.strip_prefix("bot=") {
+        Some(command) => !command.trim().is_empty(),
+        None => ai_from_name(name).is_some(),
+    }
+}
+
+/// starts the external bot process of the command
+fn spawn_bot(command: &str, interactive: bool) -> Option<Box<dyn Ai>> {
+    match ExternalAi::spawn(command) {
+        Ok(mut ai) => {
+            ai.interactive = interactive;
+            Some(Box::new(ai))
+        }
+
+        Err(e) => {
+            eprintln!("BOT: failed to start {:?}: {}", command, e);
+            None
+        }
+    }
+}
+
+/// same as ai_from_name, but the search cpu stops early once it hits its time limit
+/// and bots are polled instead of waited for, so the window never stalls
+pub fn interactive_ai_from_name(name: &str) -> Option<Box<dyn Ai>> {
+    match name {
+        "search" => Some(Box::new(SearchAi::interactive())),
+        _ if name.starts_with("bot=") => spawn_bot(&name[4..], true),
+        _ => ai_from_name(name),
+    }
+}
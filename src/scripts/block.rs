@@ -1,7 +1,7 @@
 use crate::helpers::*;
 use BlockState::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum BlockState {
     Idle,
     Hang {
@@ -23,7 +23,23 @@ pub enum BlockState {
     Spawned,
 }
 
+impl BlockState {
+    /// short state name with its counter, drawn on each block while paused
+    pub fn short_name(&self) -> String {
+        match self {
+            Idle => String::new(),
+            Hang { counter } => format!("h{}", counter),
+            Fall => "f".to_string(),
+            Swap { counter, .. } => format!("s{}", counter),
+            Land { counter } => format!("l{}", counter),
+            Clear { counter, .. } => format!("c{}", counter),
+            Spawned => "n".to_string(),
+        }
+    }
+}
+
 /// block data used for unique block rendering and unique state
+#[derive(Clone)]
 pub struct Block {
     /// hframe horizontal position in the texture atlas
     pub hframe: u32,
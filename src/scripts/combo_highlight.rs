@@ -26,6 +26,7 @@ pub struct ComboData {
 }
 
 /// list of combo data and draw info
+#[derive(Clone)]
 pub struct ComboHighlight {
     pub list: VecDeque<ComboData>,
     dimensions: V2,
@@ -106,11 +107,16 @@ impl ComboHighlight {
             };
 
             // TODO(Skytrias): implement from(f32, f32) for V2
+            // centered on the combo sprite
             sprites.text(Text {
                 content: &text,
-                position: offset_position + self.dimensions / 2.,
-                // TODO(Skytrias): align
+                position: offset_position
+                    + v2(
+                        self.dimensions.x / 2.,
+                        (self.dimensions.y - ATLAS_TILE * stupid_scale) / 2.,
+                    ),
                 scale: V2::broadcast(stupid_scale),
+                align: Align::Center,
                 ..Default::default()
             });
 
@@ -2,6 +2,7 @@ use crate::helpers::*;
 use crate::scripts::{Block, BlockState, Child};
 
 /// variants that live in each grid space
+#[derive(Clone)]
 pub enum Component {
     /// shows up as nothing in the grid
     Empty { size: usize, alive: bool },
@@ -1,22 +1,19 @@
 use crate::engine::*;
 use crate::helpers::*;
-use crate::scripts::{BlockState, Component};
-use gilrs::Button;
-use miniquad::KeyCode;
+use crate::scripts::{BlockState, Component, Difficulty};
 use std::collections::VecDeque;
 use ultraviolet::Lerp;
 
-/// amount of frames it takes for the fast cursor movement to happen
-const FRAME_LIMIT: u32 = 25;
 /// amount of frames it takes to animate till the next cursor vframe appears
 const ANIMATION_TIME: u32 = 64;
 /// amount of frames it takes to lerp from one to the other cursor position
 const LERP_TIME: u32 = 8;
-/// amount of frames it takes to start an ai step
-const START_DELAY_TIME: u32 = 10;
-/// amount of frames it takes after the end of an ai step
-const END_DELAY_TIME: u32 = 10;
+/// fill colors of the plan overlay, blue moves, grey transports and red highlighted blocks
+const PLAN_MOVE_HFRAME: u32 = 6;
+const PLAN_TRANSPORT_HFRAME: u32 = 4;
+const PLAN_HIGHLIGHT_HFRAME: u32 = 5;
 
+#[derive(Clone)]
 pub enum CursorState {
     Idle,
     MoveSwap {
@@ -33,6 +30,7 @@ pub enum CursorState {
 }
 
 /// the player controls the cursor, holds sprite and position data
+#[derive(Clone)]
 pub struct Cursor {
     sprite: Sprite,
 
@@ -46,6 +44,15 @@ pub struct Cursor {
 
     pub ai: bool,
 
+    /// index of the player bindings that control the cursor
+    pub player: usize,
+
+    /// frames left and cursor position of a swap that waits for the blocks to be swappable
+    pub buffered_swap: Option<(u32, I2)>,
+
+    /// swaps left when playing a puzzle field, none is unlimited
+    pub swaps_left: Option<u32>,
+
     /// ai state
     //pub state: CursorState,
     pub states: VecDeque<CursorState>,
@@ -53,12 +60,29 @@ pub struct Cursor {
     /// delay the ai for lower speed
     pub start_delay: u32,
     pub end_delay: u32,
+
+    /// evaluation score of the plan the states came from, if the ai rates its plans
+    pub plan_score: Option<i32>,
+
+    /// timings and mistake chance of the ai
+    pub difficulty: Difficulty,
+
+    /// random number generator for ai mistakes
+    rng: oorandom::Rand32,
 }
 
-pub fn move_to(counter: &mut u32, current: &mut I2, goal: I2) -> bool {
+/// moves the current position one step closer to the goal on the first frame,
+/// then every move repeat frames once the move delay of the difficulty passed
+pub fn move_to(counter: &mut u32, current: &mut I2, goal: I2, difficulty: Difficulty) -> bool {
     *counter += 1;
 
-    if *counter == 1 || *counter > FRAME_LIMIT {
+    let delay = difficulty.move_delay;
+    if *counter == 1 || *counter >= delay + difficulty.move_repeat {
+        // NOTE(Skytrias): rewind to the delay so the next move waits move repeat frames
+        if *counter > 1 {
+            *counter = delay;
+        }
+
         if current.y != goal.y {
             if current.y < goal.y {
                 current.y += 1;
@@ -105,6 +129,12 @@ impl Default for Cursor {
             start_delay: 0,
             end_delay: 0,
             ai: false,
+            player: 0,
+            buffered_swap: None,
+            swaps_left: None,
+            plan_score: None,
+            difficulty: Difficulty::default(),
+            rng: oorandom::Rand32::new(0),
         }
     }
 }
@@ -112,15 +142,36 @@ impl Default for Cursor {
 impl Cursor {
     pub fn reset(&mut self) {
         self.position = i2(2, 7);
+        self.buffered_swap = None;
+        self.swaps_left = None;
     }
 
-    pub fn new(ai: bool) -> Self {
+    /// creates the cursor with its own seed for ai mistakes
+    pub fn new(seed: u64) -> Self {
         Self {
-            ai,
+            rng: oorandom::Rand32::new(seed),
             ..Default::default()
         }
     }
 
+    /// shifts planned goals to a neighboring column, chance dependant on the difficulty
+    pub fn add_mistakes(&mut self, states: &mut VecDeque<CursorState>) {
+        for state in states.iter_mut() {
+            if let CursorState::MoveSwap { goal, .. } | CursorState::MoveTransport { goal, .. } =
+                state
+            {
+                if self.rng.rand_float() < self.difficulty.mistake_rate {
+                    let shift = if self.rng.rand_range(0..2) == 0 {
+                        -1
+                    } else {
+                        1
+                    };
+                    goal.x = (goal.x + shift).max(0).min(GRID_WIDTH as i32 - 2);
+                }
+            }
+        }
+    }
+
     /// input update which controls the movement of the cursor and also swapping of blocks in the grid
     pub fn update(&mut self, input: &Input, components: &mut Vec<Component>) {
         if self.counter < ANIMATION_TIME - 1 {
@@ -152,16 +203,17 @@ impl Cursor {
     }
 
     fn update_player(&mut self, input: &Input, components: &mut Vec<Component>) {
-        let left = input.kb_down_frames(KeyCode::Left, Button::DPadLeft);
-        let right = input.kb_down_frames(KeyCode::Right, Button::DPadRight);
-        let up = input.kb_down_frames(KeyCode::Up, Button::DPadUp);
-        let down = input.kb_down_frames(KeyCode::Down, Button::DPadDown);
+        let left = input.control_down_frames(self.player, Control::Left);
+        let right = input.control_down_frames(self.player, Control::Right);
+        let up = input.control_down_frames(self.player, Control::Up);
+        let down = input.control_down_frames(self.player, Control::Down);
 
-        // movement dependant on how long a key down has been held for in frames
+        // movement dependant on how long a key down has been held for in frames, repeats as set up by the player
+        let bindings = input.bindings(self.player);
 
         if self.position.x > 0 {
             if let Some(frame) = left {
-                if frame == 1 || frame > FRAME_LIMIT {
+                if bindings.repeats(frame) {
                     self.position.x -= 1;
                 }
             }
@@ -169,7 +221,7 @@ impl Cursor {
 
         if self.position.x < (GRID_WIDTH - 2) as i32 {
             if let Some(frame) = right {
-                if frame == 1 || frame > FRAME_LIMIT {
+                if bindings.repeats(frame) {
                     self.position.x += 1;
                 }
             }
@@ -177,7 +229,7 @@ impl Cursor {
 
         if self.position.y > 0 {
             if let Some(frame) = up {
-                if frame == 1 || frame > FRAME_LIMIT {
+                if bindings.repeats(frame) {
                     self.position.y -= 1;
                 }
             }
@@ -185,26 +237,37 @@ impl Cursor {
 
         if self.position.y < (GRID_HEIGHT - 2) as i32 {
             if let Some(frame) = down {
-                if frame == 1 || frame > FRAME_LIMIT {
+                if bindings.repeats(frame) {
                     self.position.y += 1;
                 }
             }
         }
 
-        if input.key_pressed(KeyCode::S)
-            || input.button_pressed(Button::South)
-            || input.button_pressed(Button::East)
-        {
-            self.swap_blocks(components);
+        if input.control_pressed(self.player, Control::Swap) {
+            self.buffered_swap = Some((bindings.swap_buffer, self.position));
         }
 
-        // TODO(Skytrias): REMOVE ON RELEASE, only used for debugging faster
-        if input.key_pressed(KeyCode::A) {
-            let index = self.position.to_index();
-            components.swap(index, index - GRID_WIDTH);
+        // swaps on the first legal frame, smart swaps are dropped once the cursor moved
+        if let Some((frames, position)) = self.buffered_swap {
+            if bindings.smart_swap && position != self.position {
+                self.buffered_swap = None;
+            } else if self.can_swap(components) {
+                self.swap_blocks(components);
+                self.buffered_swap = None;
+            } else if frames == 0 {
+                self.buffered_swap = None;
+            } else {
+                self.buffered_swap = Some((frames - 1, position));
+            }
         }
     }
 
+    /// true if both blocks at the cursor are idle or empty
+    pub fn can_swap(&self, components: &[Component]) -> bool {
+        let i = self.position.to_index();
+        can_swap(components, i) && can_swap(components, i + 1)
+    }
+
     pub fn update_ai(&mut self, components: &mut Vec<Component>) {
         if self.end_delay > 0 {
             self.end_delay -= 1;
@@ -216,7 +279,7 @@ impl Cursor {
         }
 
         if let Some(state) = self.states.get_mut(0) {
-            if self.start_delay < START_DELAY_TIME {
+            if self.start_delay < self.difficulty.start_delay {
                 self.start_delay += 1;
                 return;
             }
@@ -224,7 +287,7 @@ impl Cursor {
             match state {
                 CursorState::Idle => {
                     if self.states.len() == 1 {
-                        self.end_delay = END_DELAY_TIME;
+                        self.end_delay = self.difficulty.end_delay;
                     }
 
                     self.states.pop_front();
@@ -234,7 +297,7 @@ impl Cursor {
                 }
 
                 CursorState::MoveSwap { counter, goal } => {
-                    move_to(counter, &mut self.position, *goal);
+                    move_to(counter, &mut self.position, *goal, self.difficulty);
 
                     if self.position == *goal {
                         *state = CursorState::Idle;
@@ -259,7 +322,7 @@ impl Cursor {
                             if let BlockState::Clear { counter, .. } = block_state {
                                 if *counter == 0 {
                                     self.states.clear();
-                                    self.end_delay = END_DELAY_TIME;
+                                    self.end_delay = self.difficulty.end_delay;
                                     return;
                                 }
                             }
@@ -274,9 +337,9 @@ impl Cursor {
                             *counter = 0;
                             self.start_delay = 0;
                         } else {
-                            move_to(counter, &mut self.position, *goal);
+                            move_to(counter, &mut self.position, *goal, self.difficulty);
                         }
-                    } else if *counter < FRAME_LIMIT {
+                    } else if *counter < self.difficulty.move_delay {
                         *counter += 1;
                     } else {
                         if self.position == *start {
@@ -333,25 +396,7 @@ impl Cursor {
         sprites.push(self.sprite);
 
         if self.ai {
-            if let Some(state) = self.states.get_mut(0) {
-                match state {
-                    CursorState::MoveSwap { .. } => {
-                        //let goal = v2(goal.x as f32, goal.y as f32);
-
-                        /*
-                           sprites.push(Line {
-                              start: self.sprite.position + offset,
-                              end: goal * ATLAS_SPACING + offset + ATLAS_SPACING / 2.,
-                              thickness: 15.,
-                              hframe: 8,
-                              ..Default::default()
-                           });
-                        */
-                    }
-
-                    _ => {}
-                }
-
+            if let Some(state) = self.states.front() {
                 let text = match state {
                     CursorState::Idle => "I",
                     CursorState::MoveSwap { .. } => "M",
@@ -368,11 +413,28 @@ impl Cursor {
         }
     }
 
-    pub fn swap_blocks(&self, components: &mut Vec<Component>) {
+    /// draws the queued states and the plan score of the ai
+    pub fn draw_plan(&self, sprites: &mut Sprites, offset: V2) {
+        draw_plan(
+            sprites,
+            offset + v2(0., self.y_offset),
+            self.position,
+            &self.states,
+            self.plan_score,
+        );
+    }
+
+    /// swaps the blocks at the cursor, each swap started uses up one of the swaps left
+    pub fn swap_blocks(&mut self, components: &mut Vec<Component>) {
+        if self.swaps_left == Some(0) {
+            return;
+        }
+
         let i = self.position.to_index();
 
         let right = can_swap(components, i + 1);
         let left = can_swap(components, i);
+        let mut swapped = false;
 
         if right {
             if let Component::Block { state, .. } = &mut components[i] {
@@ -381,6 +443,7 @@ impl Cursor {
                         counter: 0,
                         direction: 1,
                     };
+                    swapped = true;
                 }
             }
         }
@@ -392,9 +455,112 @@ impl Cursor {
                         counter: 0,
                         direction: -1,
                     };
+                    swapped = true;
                 }
             }
         }
+
+        if let Some(swaps) = self.swaps_left.as_mut().filter(|_| swapped) {
+            *swaps -= 1;
+        }
+    }
+}
+
+/// draws states as arrows from the position through each goal, highlights the blocks that will be swapped
+/// and the evaluation score of the plan next to the position
+pub fn draw_plan(
+    sprites: &mut Sprites,
+    offset: V2,
+    position: I2,
+    states: &VecDeque<CursorState>,
+    score: Option<i32>,
+) {
+    // center between the 2 blocks of a cursor position
+    let center =
+        |position: I2| offset + v2(position.x as f32 + 1., position.y as f32 + 0.5) * ATLAS_SPACING;
+
+    let mut from = center(position);
+
+    for state in states.iter() {
+        match state {
+            CursorState::Idle => {}
+
+            CursorState::MoveSwap { goal, .. } => {
+                draw_arrow(sprites, from, center(*goal), PLAN_MOVE_HFRAME);
+                highlight(sprites, offset, *goal, 2);
+                from = center(*goal);
+            }
+
+            // moves to the goal, then carries the block back to the start
+            CursorState::MoveTransport { start, goal, .. } => {
+                draw_arrow(sprites, from, center(*goal), PLAN_MOVE_HFRAME);
+                draw_arrow(
+                    sprites,
+                    center(*goal),
+                    center(*start),
+                    PLAN_TRANSPORT_HFRAME,
+                );
+                highlight(sprites, offset, *goal, 1);
+                from = center(*start);
+            }
+        }
+    }
+
+    if let Some(score) = score {
+        sprites.text(Text {
+            content: &score.to_string(),
+            position: center(position) + v2(-ATLAS_TILE, ATLAS_TILE / 2.),
+            scale: V2::broadcast(0.5),
+            ..Default::default()
+        });
+    }
+}
+
+/// line with 2 short strokes at its end pointing back
+fn draw_arrow(sprites: &mut Sprites, start: V2, end: V2, hframe: u32) {
+    if (end - start).mag_sq() < 1. {
+        return;
+    }
+
+    let line = Line {
+        start,
+        end,
+        thickness: 4.,
+        hframe,
+        ..Default::default()
+    };
+    sprites.line(line);
+
+    let back = (start - end).normalized() * ATLAS_TILE / 3.;
+    for &angle in [-0.5f32, 0.5].iter() {
+        let (sin, cos) = angle.sin_cos();
+        sprites.line(Line {
+            start: end,
+            end: end + v2(back.x * cos - back.y * sin, back.x * sin + back.y * cos),
+            ..line
+        });
+    }
+}
+
+/// outlines the amount of blocks starting at the position
+fn highlight(sprites: &mut Sprites, offset: V2, position: I2, width: usize) {
+    let top_left = offset + v2(position.x as f32, position.y as f32) * ATLAS_SPACING;
+    let size = v2(width as f32, 1.) * ATLAS_SPACING;
+    let corners = [
+        top_left,
+        top_left + v2(size.x, 0.),
+        top_left + size,
+        top_left + v2(0., size.y),
+    ];
+
+    for i in 0..corners.len() {
+        sprites.line(Line {
+            start: corners[i],
+            end: corners[(i + 1) % corners.len()],
+            thickness: 2.,
+            hframe: PLAN_HIGHLIGHT_HFRAME,
+            ..Default::default()
+        });
     }
 }
 
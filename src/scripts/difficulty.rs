@@ -0,0 +1,142 @@
+/// cpu timings and skill, the cursor waits with these timings and the ai plans with them
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Difficulty {
+    pub name: &'static str,
+
+    /// frames the cursor waits before running each planned state, decision latency
+    pub start_delay: u32,
+
+    /// frames the cursor waits after a plan is finished
+    pub end_delay: u32,
+
+    /// frames the cursor waits after its first move towards a goal before moving on, also the wait between carried swaps
+    pub move_delay: u32,
+
+    /// frames between cursor moves once the move delay passed, sets the actions per minute
+    pub move_repeat: u32,
+
+    /// amount of swaps the search cpu looks ahead
+    pub depth: usize,
+
+    /// chance from 0 to 1 that a planned swap is shifted to a neighbor
+    pub mistake_rate: f32,
+}
+
+/// all difficulty tiers from easiest to hardest
+pub const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty {
+        name: "easy",
+        start_delay: 30,
+        end_delay: 40,
+        move_delay: 25,
+        move_repeat: 8,
+        depth: 1,
+        mistake_rate: 0.25,
+    },
+    Difficulty {
+        name: "normal",
+        start_delay: 10,
+        end_delay: 10,
+        move_delay: 25,
+        move_repeat: 1,
+        depth: 2,
+        mistake_rate: 0.1,
+    },
+    Difficulty {
+        name: "hard",
+        start_delay: 5,
+        end_delay: 5,
+        move_delay: 8,
+        move_repeat: 1,
+        depth: 3,
+        mistake_rate: 0.03,
+    },
+    Difficulty {
+        name: "expert",
+        start_delay: 1,
+        end_delay: 1,
+        move_delay: 1,
+        move_repeat: 1,
+        depth: 3,
+        mistake_rate: 0.,
+    },
+];
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        DIFFICULTIES[1]
+    }
+}
+
+impl Difficulty {
+    /// finds the tier with the name, i.e. "hard"
+    pub fn from_name(name: &str) -> Option<Self> {
+        DIFFICULTIES.iter().find(|d| d.name == name).copied()
+    }
+
+    /// next harder tier, wraps around to the easiest one
+    pub fn next(&self) -> Self {
+        let index = DIFFICULTIES
+            .iter()
+            .position(|d| d.name == self.name)
+            .unwrap_or(0);
+        DIFFICULTIES[(index + 1) % DIFFICULTIES.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::*;
+    use crate::scripts::{move_to, Cursor, CursorState};
+    use std::collections::VecDeque;
+
+    /// tiers are found by name and cycle around
+    #[test]
+    fn difficulty_tiers() {
+        assert_eq!(Difficulty::from_name("hard"), Some(DIFFICULTIES[2]));
+        assert_eq!(Difficulty::from_name("impossible"), None);
+        assert_eq!(DIFFICULTIES[3].next(), DIFFICULTIES[0]);
+    }
+
+    /// the default tier moves on the first frame and each frame after the delay, expert moves every frame
+    #[test]
+    fn difficulty_move_timing() {
+        let moves = |difficulty: Difficulty| {
+            let (mut counter, mut position) = (0, i2(0, 0));
+            (1..=40)
+                .filter(|_| move_to(&mut counter, &mut position, i2(5, 0), difficulty))
+                .collect::<Vec<u32>>()
+        };
+
+        assert_eq!(moves(Difficulty::default()), vec![1, 26, 27, 28, 29]);
+        assert_eq!(
+            moves(Difficulty::from_name("expert").unwrap()),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(moves(Difficulty::from_name("easy").unwrap()), vec![1, 33]);
+    }
+
+    /// a certain mistake always moves the goal to a neighbor column
+    #[test]
+    fn difficulty_mistakes() {
+        let mut cursor = Cursor::new(3);
+        cursor.difficulty.mistake_rate = 1.;
+
+        let mut states = VecDeque::new();
+        states.push_back(CursorState::MoveSwap {
+            counter: 0,
+            goal: i2(2, 5),
+        });
+        cursor.add_mistakes(&mut states);
+
+        match states.front() {
+            Some(CursorState::MoveSwap { goal, .. }) => {
+                assert_eq!((goal.x - 2).abs(), 1);
+                assert_eq!(goal.y, 5);
+            }
+
+            _ => panic!("mistake should keep the move swap"),
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::engine::*;
+use crate::helpers::*;
+use crate::scripts::{Component, Field, GarbageSystem, Grid, NameEntry, NameEvent};
+use miniquad::KeyCode;
+
+/// block color keys, in vframe order
+const COLOR_KEYS: [KeyCode; 5] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+];
+
+/// characters a typed file path can have
+const PATH_LENGTH: usize = 40;
+
+/// what the left mouse button places into the grid
+#[derive(Copy, Clone, PartialEq)]
+pub enum Brush {
+    /// places a block with the vframe
+    Block(u32),
+
+    /// drag a rectangle to place garbage
+    Garbage,
+
+    /// removes blocks and whole garbages
+    Erase,
+}
+
+/// field editor, the mouse places / removes blocks and garbage in a grid, saves and loads field files
+///
+/// 1 - 5 block colors, G garbage, E erase, right click erases too
+/// + / - changes the puzzle swap limit, S saves, L loads, N renames the file
+pub struct Editor {
+    pub brush: Brush,
+
+    /// grid position where the garbage drag started
+    drag_start: Option<I2>,
+
+    /// file path the field is saved to / loaded from
+    pub path: String,
+
+    /// swap limit saved with the field, turns it into a puzzle
+    pub moves: Option<u32>,
+
+    /// info text of the last save / load
+    message: &'static str,
+
+    /// open while a new file path is typed
+    naming: Option<NameEntry>,
+}
+
+impl Editor {
+    pub fn new(path: String) -> Self {
+        Self {
+            brush: Brush::Block(3),
+            drag_start: None,
+            path,
+            moves: None,
+            message: "",
+            naming: None,
+        }
+    }
+
+    /// true while a file path is typed, all other shortcuts wait
+    pub fn naming(&self) -> bool {
+        self.naming.is_some()
+    }
+
+    /// loads the field from the path into the grid, keeps the grid on failure
+    pub fn load(&mut self, grid: &mut Grid, garbage_system: &mut GarbageSystem) {
+        match Field::load(&self.path) {
+            Ok(field) => {
+                field.apply(grid, garbage_system);
+                self.moves = field.moves;
+                self.message = "loaded";
+            }
+
+            Err(e) => {
+                eprintln!("EDITOR: failed to load {}: {}", self.path, e);
+                self.message = "load failed";
+            }
+        }
+    }
+
+    /// saves the grid to the path
+    pub fn save(&mut self, grid: &Grid, garbage_system: &GarbageSystem) {
+        let mut field = Field::from_grid(grid, garbage_system);
+        field.moves = self.moves;
+
+        match field.save(&self.path) {
+            Ok(()) => self.message = "saved",
+
+            Err(e) => {
+                eprintln!("EDITOR: failed to save {}: {}", self.path, e);
+                self.message = "save failed";
+            }
+        }
+    }
+
+    /// grid position of the mouse if it is inside the grid
+    fn mouse_position(input: &Input, offset: V2) -> Option<I2> {
+        let position = (input.mouse.position - offset) / ATLAS_SPACING;
+        let (x, y) = (position.x.floor() as i32, position.y.floor() as i32);
+
+        if x >= 0 && x < GRID_WIDTH as i32 && y >= 0 && y < GRID_HEIGHT as i32 {
+            Some(i2(x, y))
+        } else {
+            None
+        }
+    }
+
+    /// removes whatever lives at the index, whole garbages included
+    fn erase(grid: &mut Grid, garbage_system: &mut GarbageSystem, index: usize) {
+        if let Component::Child(_) = &grid[index] {
+            grid.remove_garbage_at(garbage_system, index);
+        }
+
+        grid[index] = Component::spawn(None);
+    }
+
+    /// places a garbage rectangle between both positions, 2d garbage spans the whole width
+    fn place_garbage(grid: &mut Grid, garbage_system: &mut GarbageSystem, start: I2, end: I2) {
+        let min = i2(start.x.min(end.x), start.y.min(end.y));
+        let max = i2(start.x.max(end.x), start.y.max(end.y));
+        let height = (max.y - min.y + 1) as usize;
+
+        let (x, width) = if height > 1 {
+            (0, GRID_WIDTH)
+        } else {
+            (min.x, (max.x - min.x + 1).max(3) as usize)
+        };
+        let x = x.min((GRID_WIDTH - width) as i32);
+
+        for y in min.y..=max.y {
+            for x in x..x + width as i32 {
+                Editor::erase(grid, garbage_system, i2(x, y).to_index());
+            }
+        }
+
+        grid.spawn_garbage(garbage_system, i2(x, min.y), width, height);
+    }
+
+    /// edits the grid with the mouse, the grid itself isn't updated
+    pub fn update(
+        &mut self,
+        input: &Input,
+        grid: &mut Grid,
+        garbage_system: &mut GarbageSystem,
+        offset: V2,
+    ) {
+        if let Some(naming) = &mut self.naming {
+            match naming.update(input) {
+                Some(NameEvent::Confirmed(path)) => {
+                    self.path = path;
+                    self.message = "renamed";
+                    self.naming = None;
+                }
+
+                Some(NameEvent::Cancelled) => self.naming = None,
+                None => {}
+            }
+
+            return;
+        }
+
+        if input.key_pressed(KeyCode::N) {
+            self.naming = Some(NameEntry::new(&self.path, PATH_LENGTH));
+            return;
+        }
+
+        for (i, &key) in COLOR_KEYS.iter().enumerate() {
+            if input.key_pressed(key) {
+                self.brush = Brush::Block(3 + i as u32);
+            }
+        }
+
+        if input.key_pressed(KeyCode::G) {
+            self.brush = Brush::Garbage;
+        }
+
+        if input.key_pressed(KeyCode::E) {
+            self.brush = Brush::Erase;
+        }
+
+        if input.key_pressed(KeyCode::Equal) {
+            self.moves = Some(self.moves.unwrap_or(0) + 1);
+        }
+
+        if input.key_pressed(KeyCode::Minus) {
+            self.moves = self.moves.filter(|&moves| moves > 1).map(|moves| moves - 1);
+        }
+
+        if input.key_pressed(KeyCode::S) {
+            self.save(grid, garbage_system);
+        }
+
+        if input.key_pressed(KeyCode::L) {
+            self.load(grid, garbage_system);
+        }
+
+        let position = Editor::mouse_position(input, offset);
+
+        if input.mouse.left_released {
+            if let (Some(start), Some(end)) = (self.drag_start.take(), position) {
+                Editor::place_garbage(grid, garbage_system, start, end);
+            }
+        }
+
+        let position = match position {
+            Some(position) => position,
+            None => return,
+        };
+        let index = position.to_index();
+
+        if input.mouse.right_down {
+            Editor::erase(grid, garbage_system, index);
+            return;
+        }
+
+        match self.brush {
+            Brush::Block(vframe) => {
+                if input.mouse.left_down {
+                    Editor::erase(grid, garbage_system, index);
+                    grid[index] = Component::spawn(Some(vframe));
+                }
+            }
+
+            Brush::Garbage => {
+                if input.mouse.left_pressed {
+                    self.drag_start = Some(position);
+                }
+            }
+
+            Brush::Erase => {
+                if input.mouse.left_down {
+                    Editor::erase(grid, garbage_system, index);
+                }
+            }
+        }
+    }
+
+    /// draws the brush preview under the mouse and the editor info
+    pub fn draw(&self, sprites: &mut Sprites, input: &Input, offset: V2) {
+        if let Some(position) = Editor::mouse_position(input, offset) {
+            let (start, end) = match self.drag_start {
+                Some(start) if start.y != position.y => {
+                    (i2(0, start.y), i2(GRID_WIDTH as i32 - 1, position.y))
+                }
+                Some(start) => (start, position),
+                None => (position, position),
+            };
+
+            let min = i2(start.x.min(end.x), start.y.min(end.y));
+            let max = i2(start.x.max(end.x), start.y.max(end.y));
+
+            let vframe = match self.brush {
+                Brush::Block(vframe) => vframe,
+                Brush::Garbage => ATLAS_GARBAGE_1D,
+                Brush::Erase => ATLAS_FILL,
+            };
+
+            sprites.push(Sprite {
+                position: offset + v2(min.x as f32, min.y as f32) * ATLAS_SPACING,
+                vframe,
+                hframe: if let Brush::Erase = self.brush { 7 } else { 0 },
+                scale: v2((max.x - min.x + 1) as f32, (max.y - min.y + 1) as f32),
+                depth: 0.05,
+                ..Default::default()
+            });
+        }
+
+        let text_position = offset + v2(GRID_WIDTH as f32 + 1., 0.) * ATLAS_SPACING;
+        let brush = match self.brush {
+            Brush::Block(_) => "block",
+            Brush::Garbage => "garbage",
+            Brush::Erase => "erase",
+        };
+
+        sprites.text(Text {
+            content: brush,
+            position: text_position,
+            ..Default::default()
+        });
+
+        if let Some(moves) = self.moves {
+            sprites.text(Text {
+                content: &format!("moves {}", moves),
+                position: text_position + v2(0., ATLAS_TILE),
+                ..Default::default()
+            });
+        }
+
+        sprites.text(Text {
+            content: self.message,
+            position: text_position + v2(0., ATLAS_TILE * 2.),
+            ..Default::default()
+        });
+
+        if let Some(naming) = &self.naming {
+            naming.draw(
+                sprites,
+                text_position + v2(0., ATLAS_TILE * 3.),
+                V2::broadcast(0.5),
+            );
+        }
+    }
+}
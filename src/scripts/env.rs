@@ -0,0 +1,301 @@
+use crate::engine::Input;
+use crate::helpers::*;
+use crate::scripts::{
+    match_grids, update_versus, BlockState, Component, Contestant, GarbageSystem, Grid,
+    FIRST_VFRAME, LAST_VFRAME, MATCH_FRAME_LIMIT,
+};
+use std::io::{BufRead, Write};
+
+/// amount of block colors, one plane each
+const COLOR_PLANES: usize = (LAST_VFRAME - FIRST_VFRAME + 1) as usize;
+/// idle, swap, hang, fall, land, clear and spawned, one plane each
+const STATE_PLANES: usize = 7;
+/// color planes, state planes, garbage plane and cursor plane
+const PLANES: usize = COLOR_PLANES + STATE_PLANES + 2;
+/// raise progress and the opponent's running chain
+const SCALARS: usize = 2;
+/// length of every observation
+pub const OBSERVATION_SIZE: usize = PLANES * GRID_TOTAL + SCALARS;
+
+/// frames the simulation advances for a single step by default
+const DEFAULT_FRAMES_PER_STEP: u32 = 4;
+
+/// reward weights, winning and losing outweigh everything else
+const CLEAR_REWARD: f32 = 0.1;
+const GARBAGE_REWARD: f32 = 1.;
+const WIN_REWARD: f32 = 10.;
+const LOSE_REWARD: f32 = -10.;
+
+/// everything an agent can do in a single step, the index is the discrete action number
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    Wait,
+    Up,
+    Down,
+    Left,
+    Right,
+    Swap,
+    Raise,
+}
+
+impl Action {
+    /// all actions ordered by their index
+    pub const ALL: [Action; 7] = [
+        Action::Wait,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Swap,
+        Action::Raise,
+    ];
+
+    /// parses the action index or its lowercase name
+    pub fn parse(text: &str) -> Option<Self> {
+        if let Ok(index) = text.parse::<usize>() {
+            return Action::ALL.get(index).copied();
+        }
+
+        Action::ALL
+            .iter()
+            .find(|action| format!("{:?}", action).to_lowercase() == text)
+            .copied()
+    }
+}
+
+/// flat encoding of the agents grid, always OBSERVATION_SIZE long
+///
+/// planes of GRID_TOTAL values in row order, 1 where the plane applies and 0 otherwise:
+/// 5 color planes, 7 block state planes (idle, swap, hang, fall, land, clear, spawned),
+/// the garbage plane and the cursor plane holding both swapped positions
+/// followed by the raise progress from 0 to 1 and the opponent's running chain length
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub values: Vec<f32>,
+}
+
+impl Observation {
+    /// encodes the grid
+    pub fn new(grid: &Grid) -> Self {
+        let mut values = vec![0.; OBSERVATION_SIZE];
+        let mut plane = |plane: usize, i: usize| values[plane * GRID_TOTAL + i] = 1.;
+
+        for i in 0..GRID_TOTAL {
+            match &grid[i] {
+                Component::Block { block, state } => {
+                    if block.vframe >= FIRST_VFRAME && block.vframe <= LAST_VFRAME {
+                        plane((block.vframe - FIRST_VFRAME) as usize, i);
+                    }
+
+                    let state_plane = match state {
+                        BlockState::Idle => 0,
+                        BlockState::Swap { .. } => 1,
+                        BlockState::Hang { .. } => 2,
+                        BlockState::Fall => 3,
+                        BlockState::Land { .. } => 4,
+                        BlockState::Clear { .. } => 5,
+                        BlockState::Spawned => 6,
+                    };
+                    plane(COLOR_PLANES + state_plane, i);
+                }
+
+                Component::Child(_) => plane(PLANES - 2, i),
+                Component::Empty { .. } => {}
+            }
+        }
+
+        let cursor = grid.cursor.position.to_index();
+        plane(PLANES - 1, cursor);
+        plane(PLANES - 1, cursor + 1);
+
+        values[PLANES * GRID_TOTAL] = grid.push_amount / ATLAS_TILE;
+        values[PLANES * GRID_TOTAL + 1] = grid.opponent_chain as f32;
+        Self { values }
+    }
+}
+
+/// gym style environment over the real simulation, the agent plays the first grid
+/// optionally against a cpu on the second grid that sends garbage
+pub struct Env {
+    pub grids: Vec<Grid>,
+    pub garbage_system: GarbageSystem,
+
+    /// cpu on the second grid, none lets the agent play alone
+    pub opponent: Option<Contestant>,
+
+    /// frames the simulation advances each step
+    pub frames_per_step: u32,
+
+    /// frames since the last reset
+    pub frames: u32,
+
+    input: Input,
+}
+
+impl Env {
+    pub fn new(opponent: Option<Contestant>) -> Self {
+        let mut env = Self {
+            grids: Vec::new(),
+            garbage_system: GarbageSystem::default(),
+            opponent,
+            frames_per_step: DEFAULT_FRAMES_PER_STEP,
+            frames: 0,
+            input: Input::headless(),
+        };
+
+        env.reset(0);
+        env
+    }
+
+    /// starts a new seeded match, the same seed and actions always play out the same
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.grids = match_grids(seed);
+        self.garbage_system = GarbageSystem::default();
+        self.frames = 0;
+
+        match &self.opponent {
+            Some(opponent) => opponent.control(&mut self.grids[1]),
+            None => self.grids.truncate(1),
+        }
+
+        Observation::new(&self.grids[0])
+    }
+
+    /// applies the action and advances the simulation
+    /// rewards cleared blocks and sent garbage, winning and losing end the match
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        let grid = &mut self.grids[0];
+        let (cleared, sent) = (grid.cleared_blocks, grid.garbage_sent);
+        let cursor = &mut grid.cursor;
+
+        match action {
+            Action::Wait => {}
+            Action::Up => cursor.position.y = (cursor.position.y - 1).max(0),
+            Action::Down => cursor.position.y = (cursor.position.y + 1).min(GRID_HEIGHT as i32 - 2),
+            Action::Left => cursor.position.x = (cursor.position.x - 1).max(0),
+            Action::Right => cursor.position.x = (cursor.position.x + 1).min(GRID_WIDTH as i32 - 2),
+            Action::Swap => cursor.swap_blocks(&mut grid.components),
+            Action::Raise => grid.push_raise = true,
+        }
+
+        let mut reward = 0.;
+        let mut done = false;
+
+        for _ in 0..self.frames_per_step {
+            update_versus(&self.input, &mut self.grids, &mut self.garbage_system);
+            self.frames += 1;
+
+            if self.grids[0].topped_out {
+                reward += LOSE_REWARD;
+                done = true;
+            } else if self.grids.iter().skip(1).any(|grid| grid.topped_out) {
+                reward += WIN_REWARD;
+                done = true;
+            }
+
+            if done || self.frames >= MATCH_FRAME_LIMIT {
+                done = true;
+                break;
+            }
+        }
+
+        let grid = &self.grids[0];
+        reward += (grid.cleared_blocks - cleared) as f32 * CLEAR_REWARD
+            + (grid.garbage_sent - sent) as f32 * GARBAGE_REWARD;
+
+        (Observation::new(grid), reward, done)
+    }
+}
+
+/// runs the environment over stdin / stdout for training from other languages, "env [CPU[:DIFFICULTY]]"
+///
+/// reads "reset SEED" or "step ACTION" lines, ACTION being the index or name of the action
+/// answers each with a single line "REWARD DONE VALUES...", reset answers with reward and done 0
+/// invalid lines are answered with "error MESSAGE"
+pub fn run_env(args: &[String]) {
+    let opponent = match args.first().map(|arg| Contestant::parse(arg)) {
+        Some(Ok(contestant)) => Some(contestant),
+
+        Some(Err(e)) => {
+            eprintln!("ENV: {}", e);
+            return;
+        }
+
+        None => None,
+    };
+
+    let mut env = Env::new(opponent);
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+
+    for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+        let mut words = line.split_whitespace();
+
+        let reply = match (words.next(), words.next()) {
+            (Some("reset"), seed) => match seed.unwrap_or("0").parse::<u64>() {
+                Ok(seed) => Ok((env.reset(seed), 0., false)),
+                Err(_) => Err(format!("invalid seed {:?}", seed)),
+            },
+
+            (Some("step"), Some(action)) => match Action::parse(action) {
+                Some(action) => Ok(env.step(action)),
+                None => Err(format!("unknown action {:?}", action)),
+            },
+
+            _ => Err(format!("unknown command {:?}", line)),
+        };
+
+        let text = match reply {
+            Ok((observation, reward, done)) => {
+                let values: Vec<String> =
+                    observation.values.iter().map(|v| v.to_string()).collect();
+                format!("{} {} {}", reward, done as u8, values.join(" "))
+            }
+
+            // still answer, so clients waiting for a line never hang
+            Err(e) => format!("error {}", e),
+        };
+
+        if writeln!(stdout, "{}", text)
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// observations have a fixed size and the same seed with the same actions plays out the same
+    #[test]
+    fn env_step() {
+        let mut env = Env::new(Contestant::parse("heuristic:expert").ok());
+        let observation = env.reset(3);
+        assert_eq!(observation.values.len(), OBSERVATION_SIZE);
+
+        // cursor starts at 2 7 and covers two blocks
+        let cursor = (PLANES - 1) * GRID_TOTAL + 7 * GRID_WIDTH + 2;
+        assert_eq!(observation.values[cursor], 1.);
+        assert_eq!(observation.values[cursor + 1], 1.);
+
+        let actions = [Action::Left, Action::Swap, Action::Down, Action::Swap];
+        let play = |env: &mut Env| {
+            env.reset(3);
+            (0..200)
+                .map(|i| env.step(actions[i % actions.len()]))
+                .last()
+                .unwrap()
+        };
+
+        let first = play(&mut env);
+        assert_eq!(first, play(&mut env));
+        assert_eq!(env.frames, 200 * DEFAULT_FRAMES_PER_STEP);
+
+        assert_eq!(Action::parse("raise"), Some(Action::Raise));
+        assert_eq!(Action::parse("5"), Some(Action::Swap));
+        assert_eq!(Action::parse("jump"), None);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::helpers::*;
+use crate::scripts::{Ai, Component, CursorState, Field, GarbageSystem, Grid, Plan, Threat};
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// time the game waits for a bot reply till it continues without a plan
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// running bot process, killed once the last ai using it is dropped
+struct Connection {
+    child: Child,
+    stdin: ChildStdin,
+
+    /// lines the bot wrote, read on a separate thread so the game can time out
+    replies: Receiver<String>,
+
+    /// id of the next request, replies to older requests are skipped
+    next_id: u32,
+
+    /// id and send time of the request still waiting for its reply
+    pending: Option<(u32, Instant)>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// grid controlled by an external process that talks over stdin / stdout
+///
+/// each time the cursor ran out of states the game writes a request:
+/// "plan ID", the field file of the grid, "states" followed by 12 rows of block states,
+/// "cursor X Y", "threat STACK GARBAGE_ROWS FALLING OPPONENT_CHAIN" and "end"
+/// the bot answers with a single line "ID" followed by any amount of "swap X Y" or "raise" commands
+#[derive(Clone)]
+pub struct ExternalAi {
+    /// shared by all copies of the ai, so rewinding keeps talking to the same process
+    connection: Arc<Mutex<Connection>>,
+
+    /// polls for the reply instead of waiting, the window keeps running while the bot thinks
+    pub interactive: bool,
+}
+
+impl ExternalAi {
+    /// starts the bot, the command is split by whitespace into the program and its arguments
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("empty command")?;
+
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        let stdin = child.stdin.take().ok_or("no stdin")?;
+        let stdout = child.stdout.take().ok_or("no stdout")?;
+
+        let (sender, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(Connection {
+                child,
+                stdin,
+                replies,
+                next_id: 0,
+                pending: None,
+            })),
+            interactive: false,
+        })
+    }
+}
+
+/// request text the bot receives
+pub fn describe(id: u32, grid: &Grid, garbage_system: &GarbageSystem) -> String {
+    let mut text = format!("plan {}\n", id);
+    text.push_str(&Field::from_grid(grid, garbage_system).to_string());
+    text.push_str("states\n");
+
+    for y in 0..GRID_HEIGHT {
+        for x in 0..GRID_WIDTH {
+            let c = match &grid[y * GRID_WIDTH + x] {
+                Component::Block { state, .. } => state.short_name().chars().next().unwrap_or('.'),
+                Component::Child(_) => 'g',
+                Component::Empty { .. } => '.',
+            };
+
+            text.push(c);
+        }
+
+        text.push('\n');
+    }
+
+    let threat = Threat::evaluate(grid, garbage_system);
+    let _ = writeln!(
+        text,
+        "cursor {} {}",
+        grid.cursor.position.x, grid.cursor.position.y
+    );
+    let _ = writeln!(
+        text,
+        "threat {} {} {} {}",
+        threat.stack_height,
+        threat.garbage_rows,
+        threat.garbage_falling as u8,
+        threat.opponent_chain
+    );
+    text.push_str("end\n");
+    text
+}
+
+/// parses a bot reply into its request id and the plan
+pub fn parse_reply(line: &str) -> Result<(u32, Plan), String> {
+    let mut words = line.split_whitespace();
+    let id = words
+        .next()
+        .and_then(|word| word.parse::<u32>().ok())
+        .ok_or("reply has to start with the request id")?;
+    let mut plan = Plan::default();
+
+    while let Some(word) = words.next() {
+        match word {
+            "raise" => plan.raise = true,
+
+            "swap" => {
+                let mut number = || {
+                    words
+                        .next()
+                        .and_then(|word| word.parse::<i32>().ok())
+                        .ok_or("expected swap X Y")
+                };
+                let goal = i2(number()?, number()?);
+
+                if goal.x < 0
+                    || goal.x >= GRID_WIDTH as i32 - 1
+                    || goal.y < 0
+                    || goal.y >= GRID_HEIGHT as i32 - 1
+                {
+                    return Err(format!("swap {} {} outside of the grid", goal.x, goal.y));
+                }
+
+                plan.states
+                    .push_back(CursorState::MoveSwap { counter: 0, goal });
+            }
+
+            _ => return Err(format!("unknown command {:?}", word)),
+        }
+    }
+
+    Ok((id, plan))
+}
+
+impl Ai for ExternalAi {
+    fn plan(&mut self, grid: &Grid, garbage_system: &GarbageSystem) -> Plan {
+        let mut connection = match self.connection.lock() {
+            Ok(connection) => connection,
+            Err(_) => return Plan::default(),
+        };
+
+        let (id, sent) = match connection.pending {
+            Some(pending) => pending,
+
+            None => {
+                let id = connection.next_id;
+                connection.next_id += 1;
+
+                let request = describe(id, grid, garbage_system);
+                if let Err(e) = connection.stdin.write_all(request.as_bytes()) {
+                    eprintln!("BOT: failed to write request: {}", e);
+                    return Plan::default();
+                }
+
+                connection.pending = Some((id, Instant::now()));
+                (id, Instant::now())
+            }
+        };
+
+        loop {
+            let reply = if self.interactive {
+                connection.replies.try_recv().ok()
+            } else {
+                // late replies to older requests don't extend the wait
+                let left = REPLY_TIMEOUT
+                    .checked_sub(sent.elapsed())
+                    .unwrap_or_default();
+                connection.replies.recv_timeout(left).ok()
+            };
+
+            match reply.map(|line| parse_reply(&line)) {
+                Some(Ok((reply_id, plan))) if reply_id == id => {
+                    connection.pending = None;
+                    return plan;
+                }
+
+                // late reply to an older request
+                Some(Ok(_)) => continue,
+
+                Some(Err(e)) => {
+                    eprintln!("BOT: {}", e);
+                    connection.pending = None;
+                    return Plan::default();
+                }
+
+                // no reply yet, the grid asks again after its end delay
+                None => {
+                    if sent.elapsed() >= REPLY_TIMEOUT {
+                        eprintln!("BOT: no reply to request {} in time", id);
+                        connection.pending = None;
+                    }
+
+                    return Plan::default();
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "bot"
+    }
+
+    fn clone_box(&self) -> Box<dyn Ai> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// requests hold all sections, replies turn into swaps and raises
+    #[test]
+    fn bot_protocol() {
+        let grid = Grid::new(1, 2, &Grid::gen_field(&mut oorandom::Rand32::new(3), 5));
+        let request = describe(4, &grid, &GarbageSystem::default());
+        assert!(request.starts_with("plan 4\n"));
+        assert!(request.contains("\nstates\n"));
+        assert!(request.contains("\ncursor 2 7\n"));
+        assert!(request.ends_with("end\n"));
+
+        let (id, plan) = parse_reply("4 swap 1 2 swap 3 4 raise").unwrap();
+        assert_eq!(id, 4);
+        assert_eq!(plan.states.len(), 2);
+        assert!(plan.raise);
+
+        assert!(parse_reply("swap 1 2").is_err());
+        assert!(parse_reply("1 swap 5 2").is_err());
+        assert!(parse_reply("1 jump").is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use crate::helpers::*;
+use crate::scripts::{Component, GarbageSystem, Grid};
+use std::fmt;
+
+/// lowest block vframe, saved as '1' in field files
+pub const FIRST_VFRAME: u32 = 3;
+/// highest block vframe, saved as '5' in field files
+pub const LAST_VFRAME: u32 = 7;
+
+/// rectangle of a garbage in the field, 2d garbage always spans the whole grid width
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FieldGarbage {
+    pub position: I2,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FieldGarbage {
+    /// true if the grid position lies inside the rectangle
+    pub fn contains(&self, position: I2) -> bool {
+        position.x >= self.position.x
+            && position.x < self.position.x + self.width as i32
+            && position.y >= self.position.y
+            && position.y < self.position.y + self.height as i32
+    }
+
+    /// true if both rectangles share any grid position
+    pub fn overlaps(&self, other: &FieldGarbage) -> bool {
+        self.position.x < other.position.x + other.width as i32
+            && other.position.x < self.position.x + self.width as i32
+            && self.position.y < other.position.y + other.height as i32
+            && other.position.y < self.position.y + self.height as i32
+    }
+}
+
+/// starting field or puzzle that can be saved to / loaded from a text file
+///
+/// # starts a comment
+/// "moves N" turns the field into a puzzle with a swap limit
+/// 12 rows of 6 chars, '.' is empty, '1' - '5' are block colors, 'g' marks garbage
+/// "garbage x y width height" spawns a garbage rectangle, it may not overlap other garbage or blocks
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub vframes: [Option<u32>; GRID_TOTAL],
+    pub garbages: Vec<FieldGarbage>,
+
+    /// swap limit when the field is a puzzle
+    pub moves: Option<u32>,
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Self {
+            vframes: [None; GRID_TOTAL],
+            garbages: Vec::new(),
+            moves: None,
+        }
+    }
+}
+
+impl Field {
+    /// parses the text format, returns an error message with the line number on failure
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut field = Field::default();
+        let mut y = 0;
+
+        // line number of each garbage, checked against the blocks once all rows are read
+        let mut garbage_lines = Vec::new();
+
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let error = |message: &str| format!("line {}: {}", line_number + 1, message);
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let numbers = |words: std::str::SplitWhitespace| -> Result<Vec<usize>, String> {
+                words
+                    .map(|word| word.parse::<usize>().map_err(|_| error("invalid number")))
+                    .collect()
+            };
+
+            match words.next() {
+                Some("moves") => match (words.next().map(str::parse::<u32>), words.next()) {
+                    (Some(Ok(moves)), None) => field.moves = Some(moves),
+                    (Some(Err(_)), None) => return Err(error("invalid number")),
+                    _ => return Err(error("expected moves N")),
+                },
+
+                Some("garbage") => match numbers(words)?.as_slice() {
+                    &[x, y, width, height] => {
+                        if width == 0
+                            || height == 0
+                            || x + width > GRID_WIDTH
+                            || y + height > GRID_HEIGHT
+                            || (height > 1 && width != GRID_WIDTH)
+                        {
+                            return Err(error("garbage out of bounds"));
+                        }
+
+                        let garbage = FieldGarbage {
+                            position: i2(x as i32, y as i32),
+                            width,
+                            height,
+                        };
+
+                        if field.garbages.iter().any(|g| g.overlaps(&garbage)) {
+                            return Err(error("garbage overlaps another garbage"));
+                        }
+
+                        field.garbages.push(garbage);
+                        garbage_lines.push(line_number);
+                    }
+
+                    _ => return Err(error("expected garbage x y width height")),
+                },
+
+                _ => {
+                    if y >= GRID_HEIGHT || line.chars().count() != GRID_WIDTH {
+                        return Err(error("expected 12 rows of 6 blocks"));
+                    }
+
+                    for (x, c) in line.chars().enumerate() {
+                        field.vframes[y * GRID_WIDTH + x] = match c {
+                            '.' | 'g' => None,
+
+                            '1'..='5' => Some(c.to_digit(10).unwrap() - 1 + FIRST_VFRAME),
+
+                            _ => return Err(error("unknown block")),
+                        };
+                    }
+
+                    y += 1;
+                }
+            }
+        }
+
+        if y != GRID_HEIGHT {
+            return Err(format!("expected {} rows, found {}", GRID_HEIGHT, y));
+        }
+
+        for (garbage, line_number) in field.garbages.iter().zip(garbage_lines) {
+            let covers_block =
+                (0..GRID_TOTAL).any(|i| field.vframes[i].is_some() && garbage.contains(i.to_i2()));
+
+            if covers_block {
+                return Err(format!("line {}: garbage covers blocks", line_number + 1));
+            }
+        }
+
+        Ok(field)
+    }
+
+    /// loads a field file from the path
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Field::parse(&content)
+    }
+
+    /// saves the field to the path, creates missing directories
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        std::fs::write(path, self.to_string()).map_err(|e| e.to_string())
+    }
+
+    /// copies the blocks in any state and the garbages of the grid
+    pub fn from_grid(grid: &Grid, garbage_system: &GarbageSystem) -> Self {
+        let mut field = Field::default();
+
+        for i in 0..GRID_TOTAL {
+            if let Component::Block { block, .. } = &grid[i] {
+                if block.vframe >= FIRST_VFRAME && block.vframe <= LAST_VFRAME {
+                    field.vframes[i] = Some(block.vframe);
+                }
+            }
+        }
+
+        for garbage in garbage_system.list.iter() {
+            if garbage.parent_id != grid.id || garbage.children.is_empty() {
+                continue;
+            }
+
+            let min = *garbage.children.iter().min().unwrap();
+            let max = *garbage.children.iter().max().unwrap();
+            let (min, max) = (min.to_i2(), max.to_i2());
+
+            field.garbages.push(FieldGarbage {
+                position: min,
+                width: (max.x - min.x + 1) as usize,
+                height: (max.y - min.y + 1) as usize,
+            });
+        }
+
+        field
+    }
+
+    /// replaces the grid content with the field blocks and garbages, a puzzle limits the swaps of the cursor
+    pub fn apply(&self, grid: &mut Grid, garbage_system: &mut GarbageSystem) {
+        let id = grid.id;
+        garbage_system.list.retain(|g| g.parent_id != id);
+        grid.cursor.swaps_left = self.moves;
+
+        for i in 0..GRID_TOTAL {
+            grid[i] = Component::spawn(self.vframes[i]);
+        }
+
+        for garbage in self.garbages.iter() {
+            grid.spawn_garbage(
+                garbage_system,
+                garbage.position,
+                garbage.width,
+                garbage.height,
+            );
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# everpuzzle field")?;
+
+        if let Some(moves) = self.moves {
+            writeln!(f, "moves {}", moves)?;
+        }
+
+        for y in 0..GRID_HEIGHT {
+            for x in 0..GRID_WIDTH {
+                let i = y * GRID_WIDTH + x;
+                let in_garbage = self.garbages.iter().any(|g| g.contains(i.to_i2()));
+
+                let c = match self.vframes[i] {
+                    _ if in_garbage => 'g',
+                    Some(vframe) => std::char::from_digit(vframe - FIRST_VFRAME + 1, 10).unwrap(),
+                    None => '.',
+                };
+
+                write!(f, "{}", c)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        for g in self.garbages.iter() {
+            writeln!(
+                f,
+                "garbage {} {} {} {}",
+                g.position.x, g.position.y, g.width, g.height
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripts::BlockState;
+
+    /// a saved field should load back the same
+    #[test]
+    fn field_round_trip() {
+        let mut field = Field {
+            moves: Some(2),
+            ..Default::default()
+        };
+        field.vframes[GRID_TOTAL - 1] = Some(FIRST_VFRAME);
+        field.vframes[GRID_TOTAL - 2] = Some(LAST_VFRAME);
+        field.garbages.push(FieldGarbage {
+            position: i2(1, 0),
+            width: 4,
+            height: 1,
+        });
+        field.garbages.push(FieldGarbage {
+            position: i2(0, 2),
+            width: GRID_WIDTH,
+            height: 2,
+        });
+
+        assert_eq!(Field::parse(&field.to_string()), Ok(field));
+    }
+
+    /// a puzzle field only allows its amount of swaps
+    #[test]
+    fn field_swap_limit() {
+        let mut field = Field {
+            moves: Some(1),
+            ..Default::default()
+        };
+        let bottom = GRID_TOTAL - GRID_WIDTH * 2;
+        for x in 0..GRID_WIDTH {
+            field.vframes[bottom + x] = Some(FIRST_VFRAME + x as u32 % 2);
+        }
+
+        let mut grid = Grid::empty();
+        let mut garbage_system = GarbageSystem::default();
+        field.apply(&mut grid, &mut garbage_system);
+
+        grid.cursor.position = bottom.to_i2();
+        grid.cursor.swap_blocks(&mut grid.components);
+        assert_eq!(grid.cursor.swaps_left, Some(0));
+
+        grid.cursor.position.x = 3;
+        grid.cursor.swap_blocks(&mut grid.components);
+        assert!(matches!(
+            grid[bottom + 3],
+            Component::Block {
+                state: BlockState::Idle,
+                ..
+            }
+        ));
+    }
+
+    /// rows need to be complete and garbage has to fit into the grid
+    #[test]
+    fn field_parse_errors() {
+        assert!(Field::parse("......").is_err());
+
+        let mut rows = "......\n".repeat(GRID_HEIGHT);
+        assert!(Field::parse(&rows).is_ok());
+
+        rows.push_str("garbage 4 0 3 1\n");
+        assert!(Field::parse(&rows).is_err());
+    }
+
+    /// garbage may neither overlap other garbage nor cover blocks
+    #[test]
+    fn field_parse_garbage_overlap() {
+        let rows = "......\n".repeat(GRID_HEIGHT);
+
+        let garbage = format!("{}garbage 0 0 3 1\ngarbage 3 0 3 1\n", rows);
+        assert!(Field::parse(&garbage).is_ok());
+
+        let overlap = format!(
+            "{}garbage 0 0 4 1\ngarbage 0 1 6 2\ngarbage 3 0 3 1\n",
+            rows
+        );
+        assert_eq!(
+            Field::parse(&overlap),
+            Err(format!(
+                "line {}: garbage overlaps another garbage",
+                GRID_HEIGHT + 3
+            ))
+        );
+
+        let covered = format!(
+            "garbage 0 0 3 1\n1.....\n{}",
+            "......\n".repeat(GRID_HEIGHT - 1)
+        );
+        assert_eq!(
+            Field::parse(&covered),
+            Err("line 1: garbage covers blocks".to_string())
+        );
+    }
+
+    /// moves has to fit into a u32
+    #[test]
+    fn field_parse_moves() {
+        let rows = "......\n".repeat(GRID_HEIGHT);
+
+        let moves = format!("moves 4294967295\n{}", rows);
+        assert_eq!(Field::parse(&moves).map(|f| f.moves), Ok(Some(u32::MAX)));
+
+        let overflow = format!("moves 4294967296\n{}", rows);
+        assert_eq!(
+            Field::parse(&overflow),
+            Err("line 1: invalid number".to_string())
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::helpers::*;
 use crate::scripts::{Block, BlockState, Component, Grid};
 use GarbageState::*;
 
+#[derive(Clone)]
 pub enum GarbageState {
     Idle,
 
@@ -19,6 +20,7 @@ pub enum GarbageState {
 }
 
 /// garbage child data used mainly for unqiue animation
+#[derive(Clone)]
 pub struct Child {
     /// y pixel offset of child
     pub y_offset: f32,
@@ -119,6 +121,7 @@ impl Child {
 }
 
 /// system that holds N garbages per 1 grid
+#[derive(Clone)]
 pub struct GarbageSystem {
     pub list: Vec<Garbage>,
 }
@@ -139,7 +142,7 @@ impl GarbageSystem {
         }
     }
 
-    pub fn lowest_idle(&mut self, grid: &Grid) -> Option<usize> {
+    pub fn lowest_idle(&self, grid: &Grid) -> Option<usize> {
         let mut min_y = 100_000;
 
         // TODO(Skytrias): loops through all garbage
@@ -163,7 +166,7 @@ impl GarbageSystem {
         }
     }
 
-    pub fn lowest_clear(&mut self, grid: &Grid) -> Option<usize> {
+    pub fn lowest_clear(&self, grid: &Grid) -> Option<usize> {
         let mut min_y = 100_000;
 
         for garbage in self.list.iter() {
@@ -188,6 +191,7 @@ impl GarbageSystem {
 }
 
 /// garbage that holds N indexes to garbage children in the list
+#[derive(Clone)]
 pub struct Garbage {
     /// will only update, when parent grid is calling update
     pub parent_id: usize,
@@ -359,7 +363,6 @@ impl Garbage {
                                     ..Default::default()
                                 },
                             };
-                            println!("{} hi", *child_index);
                             remove = Some(i);
                             break;
                         }
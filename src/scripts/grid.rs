@@ -4,6 +4,7 @@ use crate::scripts::*;
 use std::ops::{Index, IndexMut};
 
 /// the grid holds all components and updates all the script logic of each component  
+#[derive(Clone)]
 pub struct Grid {
     pub id: usize,
 
@@ -22,9 +23,39 @@ pub struct Grid {
     /// manual input sent, till a push_upwards has been called
     pub push_raise: bool,
 
+    /// stops all rising, even manual raises
+    pub push_frozen: bool,
+
+    /// frame time until the push_amount is increased, lower means faster rising
+    pub push_time: u32,
+
+    /// total amount of rows that have been raised by push_upwards since the last reset
+    pub raised_rows: u32,
+
+    /// set when a push_upwards was stopped because any block was still in the top row
+    pub topped_out: bool,
+
+    /// total amount of blocks that finished clearing since the last reset
+    pub cleared_blocks: u32,
+
+    /// largest chain currently running on any opponent grid, set by update_versus
+    pub opponent_chain: u32,
+
+    /// amount of garbage blocks sent to opponents since the last reset, counted by update_versus
+    pub garbage_sent: u32,
+
+    /// length of each chain done since the last reset, counted by update_versus
+    pub chains: Vec<u32>,
+
     /// cursor that the player controls inside the grid
     pub cursor: Cursor,
 
+    /// best next swaps shown to the player if enabled
+    pub hint: Hint,
+
+    /// controller that plans the cursor states, the player controls the cursor if none is set
+    pub ai: Option<Box<dyn Ai>>,
+
     /// random number generator, each grid will have its own generator, which will all use the same seed
     pub rng: oorandom::Rand32,
 }
@@ -131,12 +162,27 @@ impl Grid {
             push_counter: 0,
             push_amount: 0.,
             push_raise: false,
-
-            cursor: Cursor::new(id == 1),
+            push_frozen: false,
+            push_time: PUSH_TIME,
+            raised_rows: 0,
+            topped_out: false,
+            cleared_blocks: 0,
+            opponent_chain: 0,
+            garbage_sent: 0,
+            chains: Vec::new(),
+
+            cursor: Cursor::new(seed),
+            hint: Hint::default(),
+            ai: None,
             rng: oorandom::Rand32::new(seed),
         }
     }
 
+    /// inits the grid without any blocks
+    pub fn empty() -> Self {
+        Grid::new(0, 0, &[None; GRID_TOTAL])
+    }
+
     /// resets the grid and sets it to a new randomized field
     pub fn reset(&mut self) {
         let vframes = Grid::gen_field(&mut self.rng, 5);
@@ -148,11 +194,31 @@ impl Grid {
         self.combo_highlight.clear();
         self.push_raise = false;
         self.push_counter = 0;
+        self.push_amount = 0.;
+        self.raised_rows = 0;
+        self.topped_out = false;
+        self.cleared_blocks = 0;
+        self.garbage_sent = 0;
+        self.chains.clear();
+        self.hint.states.clear();
         self.cursor.reset();
     }
 
+    /// sets the controller of the cursor, none gives the control back to the player
+    pub fn set_ai(&mut self, ai: Option<Box<dyn Ai>>) {
+        self.cursor.ai = ai.is_some();
+        self.cursor.states.clear();
+        self.ai = ai;
+    }
+
     /// sets all blocks and childs y_offset to 0, swaps them with below and sets bottom row to randoimized blocks
     pub fn push_upwards(&mut self, garbage_system: &mut GarbageSystem) {
+        // nothing can be pushed out of the grid, a topped out grid stops rising
+        if (0..GRID_WIDTH).any(|i| !self.is_empty_at(i)) {
+            self.topped_out = true;
+            return;
+        }
+
         let vframes = self.gen_line();
 
         for x in 0..GRID_WIDTH {
@@ -180,8 +246,7 @@ impl Grid {
             }
         }
 
-        // TODO(Skytrias): detection for out of bounds?
-        // shift up the garbage children indexes
+        // shift up the garbage children indexes, none can be in the top row
         for garbage in garbage_system.list.iter_mut() {
             if garbage.parent_id == self.id {
                 for child_index in garbage.children.iter_mut() {
@@ -190,6 +255,8 @@ impl Grid {
             }
         }
 
+        self.raised_rows += 1;
+
         // shift up the cursor if still in grid range
         if self.cursor.position.y > 0 {
             self.cursor.position.y -= 1;
@@ -199,39 +266,35 @@ impl Grid {
         }
     }
 
-    /// generates a line of garbage at the top of the grid
-    pub fn gen_1d_garbage(&mut self, garbage_system: &mut GarbageSystem, width: usize) {
-        let offset = self.rng.rand_range(0..(GRID_WIDTH - width + 1) as u32) as usize;
-
-        debug_assert!(width >= 3);
-        debug_assert!(offset < GRID_WIDTH);
-
-        let children: Vec<usize> = (offset..offset + width).collect();
-
-        for (i, index) in children.iter().enumerate() {
-            let (hframe, vframe) = Child::gen_1d_frames(i, width);
-            self.components[*index] = Component::Child(Child {
-                hframe,
-                vframe,
-                ..Default::default()
-            });
-        }
-
-        garbage_system.list.push(Garbage::new(self.id, children));
-    }
-
-    /// generates a line of garbage at the top of the grid
-    pub fn gen_2d_garbage(&mut self, garbage_system: &mut GarbageSystem, height: usize) {
+    /// spawns a garbage at the position, 2d garbage always spans the whole grid width
+    pub fn spawn_garbage(
+        &mut self,
+        garbage_system: &mut GarbageSystem,
+        position: I2,
+        width: usize,
+        height: usize,
+    ) {
         debug_assert!(height >= 1);
+        debug_assert!(height == 1 || width == GRID_WIDTH);
+        debug_assert!(position.x as usize + width <= GRID_WIDTH);
 
-        let mut children = Vec::with_capacity(height * 6);
+        let mut children = Vec::with_capacity(width * height);
 
         for y in 0..height {
-            for x in 0..GRID_WIDTH {
-                let i = y * GRID_WIDTH + x;
+            for x in 0..width {
+                let i = (position.y as usize + y) * GRID_WIDTH + position.x as usize + x;
+
+                // garbage spawned over older garbage replaces it, otherwhise the older one keeps stale children
+                if let Component::Child(_) = self[i] {
+                    self.remove_garbage_at(garbage_system, i);
+                }
 
                 children.push(i);
-                let (hframe, vframe) = Child::gen_2d_frames(x, y, height);
+                let (hframe, vframe) = if height == 1 {
+                    Child::gen_1d_frames(x, width)
+                } else {
+                    Child::gen_2d_frames(x, y, height)
+                };
 
                 self.components[i] = Component::Child(Child {
                     hframe,
@@ -244,6 +307,38 @@ impl Grid {
         garbage_system.list.push(Garbage::new(self.id, children));
     }
 
+    /// generates a line of garbage at the top of the grid
+    pub fn gen_1d_garbage(&mut self, garbage_system: &mut GarbageSystem, width: usize) {
+        // combos larger than the grid send a full row
+        let width = width.min(GRID_WIDTH);
+        let offset = self.rng.rand_range(0..(GRID_WIDTH - width + 1) as u32) as usize;
+
+        debug_assert!(width >= 3);
+        debug_assert!(offset < GRID_WIDTH);
+
+        self.spawn_garbage(garbage_system, i2(offset as i32, 0), width, 1);
+    }
+
+    /// generates a line of garbage at the top of the grid
+    pub fn gen_2d_garbage(&mut self, garbage_system: &mut GarbageSystem, height: usize) {
+        self.spawn_garbage(garbage_system, I2::zero(), GRID_WIDTH, height);
+    }
+
+    /// removes the garbage which owns the child at the index, sets all its children to empty
+    pub fn remove_garbage_at(&mut self, garbage_system: &mut GarbageSystem, index: usize) {
+        let id = self.id;
+
+        if let Some(position) = garbage_system
+            .list
+            .iter()
+            .position(|g| g.parent_id == id && g.children.contains(&index))
+        {
+            for child_index in garbage_system.list.remove(position).children {
+                self[child_index] = Component::spawn(None);
+            }
+        }
+    }
+
     /// swaps the 2 index components around if the block was in swap animation
     pub fn block_resolve_swap(&mut self) {
         for y in 0..GRID_HEIGHT {
@@ -382,6 +477,7 @@ impl Grid {
                             size: block.saved_chain.unwrap_or(0) + 1,
                             alive: true,
                         };
+                        self.cleared_blocks += 1;
                     }
                 }
             }
@@ -669,527 +765,42 @@ impl Grid {
         }
     }
 
-    pub fn solve_format(&mut self, format: &[Vec<u32>]) {
-        if let Some(state) = self.cursor.states.get(0) {
-            match state {
-                CursorState::Idle => {}
-                _ => return,
-            }
-        }
-
-        let width = format[0].len();
-        let height = format.len();
-
-        // TODO(Skytrias): panic if no 2 or 3
-
-        // get how many 1s or 2s there exist
-        let mut goal_amount = 0;
-        for x in 0..width {
-            for y in 0..height {
-                let num = format[y][x];
-                if num == 1 || num == 2 {
-                    goal_amount += 1;
-                }
-            }
-        }
-
-        for &vframe in &[3, 4, 5, 6, 7] {
-            // move through arrays and search for pattern
-            for x in 0..GRID_WIDTH - (width - 1) {
-                for y in 0..GRID_HEIGHT - (height - 1) - 1 {
-                    let mut goal = 0;
-                    let mut goal_index = 0;
-
-                    for y_off in 0..height {
-                        for x_off in 0..width {
-                            let num = format[y_off][x_off];
-
-                            let i = (y + y_off) * GRID_WIDTH + (x + x_off);
-
-                            if num == 2 || num == 3 {
-                                goal_index = i;
-                            }
-
-                            if let Component::Block { block, state } = &self[i] {
-                                if let BlockState::Idle = state {
-                                    if (num == 1 || num == 2) && block.vframe == vframe {
-                                        goal += 1;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    if goal >= goal_amount {
-                        self.cursor.states.push_back(CursorState::MoveSwap {
-                            counter: 0,
-                            goal: goal_index.to_i2(),
-                        });
-                    }
-                }
-            }
-        }
-    }
-
-    pub fn solve_vertically(
-        &mut self,
-        goal_amount: usize,
-        x_start: usize,
-        x_end: usize,
-        y_start: usize,
-        y_end: usize,
-    ) {
-        debug_assert!(goal_amount != 0);
-
-        // TODO(Skytrias): reevaluate smallest distance so that states might be reset in favor
-
-        let mut smallest_distance: Option<i32> = None;
-        let mut searched_indexes = Vec::new();
-
-        for y in y_start..y_end - goal_amount {
-            'skip: for x in x_start..x_end {
-                // TODO(Skytrias): modify if more / less vframes exist
-                for &vframe in &[3, 4, 5, 6, 7] {
-                    // TODO(Skytrias): not great
-                    let mut indexes = Vec::new();
-                    let mut goal_counter = 0;
-                    let mut sum_distance = 0;
-
-                    for y_off in 0..goal_amount {
-                        'inner: for x_off in 0..GRID_WIDTH {
-                            let i = (y + y_off) * GRID_WIDTH + x;
-                            let j = (y + y_off) * GRID_WIDTH + x_off;
-
-                            if let Component::Empty { .. } = &self[i] {
-                                break 'skip;
-                            }
-
-                            if let Component::Block { block, state } = &self[j] {
-                                if let BlockState::Idle = state {
-                                    if block.vframe == vframe {
-                                        goal_counter += 1;
-                                        let distance = x as i32 - x_off as i32;
-                                        sum_distance += distance.abs();
-
-                                        if i != j {
-                                            indexes.push((i, distance));
-                                        }
-
-                                        break 'inner;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    if goal_counter == goal_amount {
-                        if let Some(distance) = smallest_distance.as_mut() {
-                            if *distance > sum_distance {
-                                *distance = sum_distance;
-                                searched_indexes = indexes;
-                            }
-                        } else {
-                            smallest_distance = Some(sum_distance);
-                            searched_indexes = indexes;
-                        }
-                    }
-                }
-            }
-        }
-
-        if let Some(smallest) = smallest_distance {
-            for (start_index, distance) in searched_indexes {
-                // move to index, dependant on the direction move further
-                let goal = if distance > 0 {
-                    start_index as i32 - smallest
-                } else {
-                    start_index as i32 - (smallest + 1)
-                };
-
-                self.cursor.states.push_back(CursorState::MoveTransport {
-                    counter: 0,
-                    reached: false,
-                    swap_end: true,
-                    start: start_index.to_i2(),
-                    goal: goal.to_i2(),
-                });
-            }
-        }
-    }
-
-    // checks wether an x path has any empty or clearing block
-    pub fn path_has_empty(&self, x_start: usize, x_end: usize, y_axis: usize) -> bool {
-        let min = x_start.min(x_end);
-        let max = x_start.max(x_end);
-
-        for x in min..max {
-            let i = y_axis * GRID_WIDTH + x;
-
-            if let Component::Empty { .. } = &self[i] {
-                if i < GRID_TOTAL - GRID_WIDTH {
-                    if let Component::Empty { .. } = &self[i + GRID_WIDTH] {
-                        return true;
-                    }
-                }
-            }
-
-            if let Component::Block { state, .. } = &self[i] {
-                if let BlockState::Clear { .. } = state {
-                    return true;
-                } else {
-                    return false;
-                }
-            }
-        }
-
-        false
-    }
-
-    pub fn solve_spawn_vertically(&mut self, y_start: usize) {
-        // TODO(Skytrias): reevaluate smallest distance so that states might be reset in favor
-
-        // only allow 3x1 pairs
-        'search: for x in 0..GRID_WIDTH {
-            let i = y_start * GRID_WIDTH + x;
-            let mut indexes = Vec::new();
-            let mut goal_counter = 0;
-
-            if let Component::Block { block, state } = &self[i] {
-                if let BlockState::Spawned = state {
-                    // search for others nearby on the next y positions below
-                    for y_off in 1..3 {
-                        // if below is already the same, skip
-                        if let Component::Block {
-                            block: below_block, ..
-                        } = &self[(y_start + y_off) * GRID_WIDTH + x]
-                        {
-                            if below_block.vframe == block.vframe {
-                                continue;
-                            }
-                        }
-
-                        'next: for x_off in 0..GRID_WIDTH {
-                            let j = (y_start + y_off) * GRID_WIDTH + x_off;
-
-                            if let Component::Block {
-                                block: below_block, ..
-                            } = &self[j]
-                            {
-                                if self.path_has_empty(x_off, x, y_start + y_off) {
-                                    continue;
-                                }
-
-                                if below_block.vframe == block.vframe {
-                                    goal_counter += 1;
-                                    indexes.push(((y_start + y_off) * GRID_WIDTH + x, j));
-
-                                    if goal_counter > 1 {
-                                        for (start, end) in &indexes {
-                                            let goal = {
-                                                let pos = end.to_i2();
-
-                                                if pos.x < self.cursor.position.x {
-                                                    pos
-                                                } else {
-                                                    i2(pos.x, pos.y)
-                                                }
-                                            };
-
-                                            self.cursor.states.push_back(
-                                                CursorState::MoveTransport {
-                                                    counter: 0,
-                                                    reached: false,
-                                                    swap_end: false,
-                                                    start: start.to_i2(),
-                                                    goal,
-                                                },
-                                            );
-                                        }
-
-                                        break 'search;
-                                    }
-
-                                    break 'next;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    pub fn solve_horizontally(&mut self, goal_amount: usize, y_start: usize, y_end: usize) {
-        debug_assert!(goal_amount != 0);
-
-        // TODO(Skytrias): reevaluate smallest distance so that states might be reset in favor
-
-        let mut smallest_distance: Option<i32> = None;
-        let mut searched_indexes = Vec::new();
-
-        for &vframe in &[3, 4, 5, 6, 7] {
-            for y in y_start..y_end {
-                for x in 0..GRID_WIDTH - goal_amount {
-                    // TODO(Skytrias): skip if x axis doesnt contain goal_amount of vframe
-
-                    // TODO(Skytrias): not great
-                    let mut indexes = Vec::new();
-                    let mut goal_counter = 0;
-                    let mut sum_distance = 0;
-
-                    for x_off in 0..GRID_WIDTH {
-                        let i = y * GRID_WIDTH + x;
-                        let j = y * GRID_WIDTH + x_off;
-
-                        // TODO(Skytrias): skip if somewhere below has a empty
-                        // TODO(Skytrias): skip if x axis has clear?
-
-                        if let Component::Block { block, state } = &self[j] {
-                            if let BlockState::Idle = state {
-                                if block.vframe == vframe {
-                                    goal_counter += 1;
-                                    let distance = x as i32 - x_off as i32;
-                                    sum_distance += distance.abs();
-
-                                    // skip if i is the same as j, the start of the search
-                                    if i != j {
-                                        indexes.push((i, distance));
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // if goal reached, check if smaller
-                    if goal_counter == goal_amount {
-                        if let Some(distance) = smallest_distance.as_mut() {
-                            if *distance > sum_distance {
-                                *distance = sum_distance;
-                                searched_indexes = indexes;
-                            }
-                        } else {
-                            smallest_distance = Some(sum_distance);
-                            searched_indexes = indexes;
-                        }
-                    }
-                }
-            }
-        }
-
-        if let Some(smallest) = smallest_distance {
-            for (start_index, distance) in searched_indexes {
-                // move to index, dependant on the direction move further
-                let goal = if distance > 0 {
-                    start_index as i32 - smallest
-                } else {
-                    start_index as i32 - (smallest + 1)
-                };
-
-                self.cursor.states.push_back(CursorState::MoveTransport {
-                    counter: 0,
-                    reached: false,
-                    swap_end: false,
-                    start: start_index.to_i2(),
-                    goal: goal.to_i2(),
-                });
-            }
-        }
-    }
-
-    // TODO(Skytrias): TAKE AVERAGE
-    /// detects the highest and lowest block y peak and returns true if the difference equals the height_difference
-    pub fn detect_difference(&self, height_difference: i32) -> Option<usize> {
-        let mut y_min = GRID_HEIGHT;
-        let mut y_max = 0;
-        let mut x_axis = 0;
-
-        for x in 0..GRID_WIDTH {
-            for y in 0..GRID_HEIGHT {
-                let i = y * GRID_WIDTH + x;
-
-                if let Component::Block { .. } = &self[i] {
-                    y_max = y_max.max(y);
-
-                    if y_min > y {
-                        y_min = y;
-                        x_axis = x;
-                    }
-
-                    break;
-                }
-            }
-        }
-
-        if (y_min as i32 - y_max as i32).abs() > height_difference {
-            Some(x_axis)
-        } else {
-            None
-        }
-    }
-
-    pub fn remove_peaks(&mut self, x_axis: usize) {
-        for y in 0..GRID_HEIGHT - 1 {
-            let i = y * GRID_WIDTH + x_axis;
-
-            // TODO(Skytrias): move based on cursor pos
-            if let Component::Block { .. } = &mut self[i] {
-                let goal = if self.cursor.position.x <= x_axis as i32 {
-                    i
-                } else {
-                    i
-                }
-                .to_i2();
-
-                self.cursor
-                    .states
-                    .push_back(CursorState::MoveSwap { counter: 0, goal });
-                return;
-            }
-        }
-    }
-
-    fn nearest_hole(&self, y_axis: usize) -> Option<usize> {
-        // TODO(Skytrias): if y == GRID_HEIGHT - 1
-
-        for x in 0..GRID_WIDTH {
-            let i = y_axis * GRID_WIDTH + x;
-
-            if let Component::Empty { .. } = &self[i] {
-                if i < GRID_TOTAL - GRID_WIDTH {
-                    if let Component::Empty { .. } = &self[i + GRID_WIDTH] {
-                        return Some(i);
-                    }
-                }
-            }
-        }
-
-        None
-    }
-
     /// updates all components in the grid and the garbage system
     pub fn update(&mut self, input: &Input, garbage_system: &mut GarbageSystem) {
         debug_assert!(!self.components.is_empty());
 
-        if false {
-            // 3ds solvers
-            let format = vec![vec![1, 0], vec![1, 0], vec![3, 1]];
-            self.solve_format(&format);
-            let format = vec![vec![0, 1], vec![0, 1], vec![2, 0]];
-            self.solve_format(&format);
-            let format = vec![vec![3, 1], vec![1, 0], vec![1, 0]];
-            self.solve_format(&format);
-            let format = vec![vec![2, 0], vec![0, 1], vec![0, 1]];
-            self.solve_format(&format);
-
-            // prioritize 4ths
-            let format = vec![vec![0, 1], vec![2, 0], vec![0, 1], vec![0, 1]];
-            self.solve_format(&format);
-            let format = vec![vec![0, 1], vec![0, 1], vec![2, 0], vec![0, 1]];
-            self.solve_format(&format);
-            let format = vec![vec![1, 0], vec![3, 1], vec![1, 0], vec![1, 0]];
-            self.solve_format(&format);
-            let format = vec![vec![1, 0], vec![1, 0], vec![3, 1], vec![1, 0]];
-            self.solve_format(&format);
-
-            // generic 3rds
-            let format = vec![vec![0, 1], vec![2, 0], vec![0, 1]];
-            self.solve_format(&format);
-            let format = vec![vec![1, 0], vec![3, 1], vec![1, 0]];
-            self.solve_format(&format);
-
-            /*
-              // very generic
-              let format = vec![
-                            vec![0, 1, 0],
-                            vec![0, 3, 1],
-                            vec![1, 0, 0],
-                            ];
-              self.solve_format(&format);
-            */
-        }
-
         self.cursor.update(input, &mut self.components);
 
-        // ai update, priority dependant
-        if self.id == 1 && !(self.cursor.states.get(0).is_some() || self.cursor.end_delay != 0) {
-            // if total block amount is lower than 3 lines of blocks, raise once
-            let amt = self
-                .components
-                .iter()
-                .filter(|c| {
-                    if let Component::Block { .. } = c {
-                        true
-                    } else {
-                        false
-                    }
-                })
-                .count();
-            if amt <= GRID_WIDTH * 4 {
-                self.push_raise = true;
-            }
-
-            // if top has big peaks, solve them by trying vertical solves
-            if let Some(x) = self.detect_difference(5) {
-                self.remove_peaks(x);
-                let x = (x as i32 - 1).max(0) as usize;
-                self.solve_vertically(3, x, x + 1, 0, GRID_HEIGHT);
-            }
-
-            if let Some(y) = garbage_system.lowest_clear(self) {
-                // prefer spawn chain preperation
-                self.solve_spawn_vertically(y + 1);
-            } else if let Some(y) = garbage_system.lowest_idle(self) {
-                // solve garbage vertically by 3 if possible, else remove peaks,
-                let max = y + 2;
-
-                // NOTE(Skytrias): hardcoded offsets
-                self.solve_horizontally(3, y + 1, y + 2);
-                self.solve_vertically(3, 0, GRID_WIDTH, y, max + 3);
-
-                if let Some(start_index) = self.nearest_hole(y + 1) {
-                    for x in 1..GRID_WIDTH {
-                        let i = (y + 1) * GRID_WIDTH + x;
-
-                        if let Component::Empty { .. } = &self[i] {
-                            continue;
-                        }
+        // ask the controller for a new plan once the cursor ran out of states
+        if self.cursor.ai && self.cursor.states.is_empty() && self.cursor.end_delay == 0 {
+            if let Some(mut ai) = self.ai.take() {
+                let mut plan = ai.plan(self, garbage_system);
 
-                        if let Component::Block { .. } = &self[i] {
-                            let goal = if self.cursor.position.x <= x as i32 {
-                                i - 1
-                            } else {
-                                i
-                            }
-                            .to_i2();
-
-                            self.cursor.states.push_back(CursorState::MoveTransport {
-                                counter: 0,
-                                reached: false,
-                                swap_end: true,
-                                start: start_index.to_i2(),
-                                goal,
-                            });
-                            break;
-                        }
-                    }
-                } else {
-                    // solve normally
-                    self.solve_horizontally(3, 0, GRID_HEIGHT - 1);
-                    self.solve_vertically(4, 0, GRID_WIDTH, 0, GRID_HEIGHT);
-                    self.solve_vertically(3, 0, GRID_WIDTH, 0, GRID_HEIGHT);
+                // nothing to do right now, only ask again after the end delay
+                if plan.states.is_empty() {
+                    self.cursor.end_delay = self.cursor.difficulty.end_delay;
                 }
 
-            // else panic and do usual stuff?
-            /*
-            	*/
-            } else {
-                // solve normally
-                //self.solve_horizontally(3, 0, GRID_HEIGHT - 1);
-                //self.solve_vertically(4, 0, GRID_WIDTH, 0, GRID_HEIGHT);
-                //self.solve_vertically(3, 0, GRID_WIDTH, 0, GRID_HEIGHT);
+                self.cursor.add_mistakes(&mut plan.states);
+                self.cursor.plan_score = plan.score;
+                self.cursor.states.extend(plan.states);
+                self.push_raise |= plan.raise;
+                self.ai = Some(ai);
             }
         }
 
+        // hints are only searched for the player
+        if !self.cursor.ai {
+            let mut hint = std::mem::take(&mut self.hint);
+            hint.update(self, garbage_system);
+            self.hint = hint;
+        }
+
+        self.simulate(garbage_system);
+    }
+
+    /// advances all components by a single frame without any cursor input, also used on copied grids by the search ai
+    pub fn simulate(&mut self, garbage_system: &mut GarbageSystem) {
         self.update_components();
 
         // NOTE(Skytrias): always do resolves before detects so there is 1 frame at minimum delay
@@ -1221,6 +832,11 @@ impl Grid {
 
     /// updates the push / raise data which offsets the grid components
     pub fn push_update(&mut self, garbage_system: &mut GarbageSystem) {
+        if self.push_frozen {
+            self.push_raise = false;
+            return;
+        }
+
         // stop pushing if any block is
         for i in 0..GRID_TOTAL {
             if let Component::Block { state, .. } = &self[i] {
@@ -1231,8 +847,11 @@ impl Grid {
             }
         }
 
-        if self.push_counter < PUSH_TIME && !self.push_raise {
+        if self.push_counter < self.push_time && !self.push_raise {
             self.push_counter += 1;
+        } else if self.topped_out {
+            // NOTE(Skytrias): a topped out grid keeps its offsets till it is reset
+            self.push_raise = false;
         } else {
             self.push_amount += 1.;
             let amt = self.push_amount;
@@ -1251,11 +870,39 @@ impl Grid {
             } else {
                 self.push_upwards(garbage_system);
                 self.push_raise = false;
-                self.push_amount = 0.;
+
+                if !self.topped_out {
+                    self.push_amount = 0.;
+                }
             }
         }
     }
 
+    /// length of the chain currently running, 0 if no chain is running
+    pub fn chain_in_progress(&self) -> u32 {
+        self.components
+            .iter()
+            .filter_map(|c| match c {
+                Component::Block {
+                    block,
+                    state: BlockState::Hang { .. } | BlockState::Fall | BlockState::Clear { .. },
+                } => block.saved_chain.map(|size| size as u32 + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// returns true if the component at the index is neither a block nor a garbage child
+    fn is_empty_at(&self, index: usize) -> bool {
+        matches!(self[index], Component::Empty { .. })
+    }
+
+    /// returns true if no block or garbage child lives above the y_axis
+    pub fn empty_above(&self, y_axis: usize) -> bool {
+        (0..y_axis.min(GRID_HEIGHT) * GRID_WIDTH).all(|i| self.is_empty_at(i))
+    }
+
     /// updates all non empty components in the grid
     pub fn update_components(&mut self) {
         for component in self.components.iter_mut() {
@@ -1265,23 +912,43 @@ impl Grid {
         }
     }
 
+    /// draws the state and frame counter of each block / garbage child, used while paused
+    pub fn draw_states(&self, sprites: &mut Sprites, offset: V2) {
+        for y in 0..GRID_HEIGHT {
+            for x in 0..GRID_WIDTH {
+                let i = y * GRID_WIDTH + x;
+
+                let (text, y_offset) = match &self[i] {
+                    Component::Block { block, state } => (state.short_name(), block.offset.y),
+                    Component::Child(child) => (format!("g{}", child.counter), child.y_offset),
+                    _ => continue,
+                };
+
+                sprites.text(Text {
+                    content: &text,
+                    scale: v2(0.3, 0.3),
+                    position: v2(
+                        x as f32 * ATLAS_TILE + offset.x + 2.,
+                        y as f32 * ATLAS_TILE + y_offset + offset.y + 20.,
+                    ),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
     /// draws all the grid components as sprite / quads
     pub fn draw(&mut self, sprites: &mut Sprites, offset: V2, debug: bool) {
         self.combo_highlight.draw(sprites, offset);
         self.cursor.draw(sprites, offset);
 
-        // ai debug draw
-        if self.id == 0 {
-            // TODO(Skytrias): asdasd
-            //let pos = v2(self.cursor.position.x as f32, self.cursor.position.y as f32);
-
-            /*
-              app.push_line(Line {
-                               start: pos * ATLAS_SPACING + offset,
-                               end: v2(6., 4.) * ATLAS_SPACING + offset,
-                               ..Default::default()
-                            });
-            */
+        // queued cpu states drawn as arrows with the plan score
+        if debug && self.cursor.ai {
+            self.cursor.draw_plan(sprites, offset);
+        }
+
+        if !self.cursor.ai {
+            self.hint.draw(sprites, self, offset);
         }
 
         // draw all grid components
@@ -1399,32 +1066,84 @@ impl IndexMut<usize> for Grid {
 mod tests {
     use super::*;
 
-    /// showcase gen_1d working
+    fn is_garbage(component: &Component) -> bool {
+        matches!(component, Component::Child(_))
+    }
+
+    fn is_empty(component: &Component) -> bool {
+        matches!(component, Component::Empty { .. })
+    }
+
+    fn is_block(component: &Component) -> bool {
+        matches!(component, Component::Block { .. })
+    }
+
+    fn idle_block() -> Component {
+        Component::Block {
+            block: Block::default(),
+            state: BlockState::Idle,
+        }
+    }
+
+    /// asserts the block state at the index with the predicate
+    fn assert_state<P: Fn(&BlockState) -> bool>(grid: &Grid, index: usize, predicate: P) {
+        if let Component::Block { state, .. } = &grid[index] {
+            assert!(predicate(state), "unexpected state {:?}", state);
+        } else {
+            panic!("no block at {}", index);
+        }
+    }
+
+    /// showcase gen_1d working, combos wider than the grid send a full row
     #[test]
     fn grid_gen_1d() {
+        let mut garbage_system = GarbageSystem::default();
+
+        for combo in 3..=GRID_WIDTH + 2 {
+            let mut grid = Grid::empty();
+            grid.gen_1d_garbage(&mut garbage_system, combo);
+
+            let width = combo.min(GRID_WIDTH);
+            let children: Vec<usize> = (0..GRID_WIDTH).filter(|&i| is_garbage(&grid[i])).collect();
+            assert_eq!(children.len(), width);
+            assert_eq!(children[width - 1] - children[0], width - 1);
+            assert!(is_empty(&grid[GRID_WIDTH]));
+        }
+    }
+
+    /// garbage spawned over older garbage replaces it
+    #[test]
+    fn grid_spawn_over_garbage() {
         let mut grid = Grid::empty();
         let mut garbage_system = GarbageSystem::default();
 
-        grid.gen_1d_garbage(&mut garbage_system, 3, 0);
-        assert!(grid[0].is_garbage());
-        assert!(grid[1].is_garbage());
-        assert!(grid[2].is_garbage());
-        assert!(grid[3].is_empty());
-
-        grid.gen_1d_garbage(&mut garbage_system, 4, 0);
-        assert!(grid[0].is_garbage());
-        assert!(grid[1].is_garbage());
-        assert!(grid[2].is_garbage());
-        assert!(grid[3].is_garbage());
-        assert!(grid[4].is_empty());
-
-        grid = Grid::empty();
-        grid.gen_1d_garbage(&mut garbage_system, 3, 1);
-        assert!(grid[0].is_empty());
-        assert!(grid[1].is_garbage());
-        assert!(grid[2].is_garbage());
-        assert!(grid[3].is_garbage());
-        assert!(grid[4].is_empty());
+        grid.spawn_garbage(&mut garbage_system, I2::zero(), 3, 1);
+        grid.spawn_garbage(&mut garbage_system, i2(2, 0), 4, 1);
+
+        assert_eq!(garbage_system.list.len(), 1);
+        assert_eq!(garbage_system.list[0].children, vec![2, 3, 4, 5]);
+        assert!(is_empty(&grid[0]));
+        assert!(is_empty(&grid[1]));
+    }
+
+    /// a topped out grid stops rising and keeps its offsets
+    #[test]
+    fn grid_topped_out_push() {
+        let mut grid = Grid::empty();
+        let mut garbage_system = GarbageSystem::default();
+        grid[0] = idle_block();
+        grid.push_raise = true;
+
+        for _ in 0..ATLAS_TILE as usize * 2 {
+            grid.push_update(&mut garbage_system);
+        }
+
+        assert!(grid.topped_out);
+        assert_eq!(grid.push_amount, ATLAS_TILE);
+        match &grid[0] {
+            Component::Block { block, .. } => assert_eq!(block.offset.y, -(ATLAS_TILE - 1.)),
+            _ => panic!("no block at 0"),
+        }
     }
 
     /// check if hang to fall works in the wanted frame times
@@ -1432,36 +1151,84 @@ mod tests {
     fn block_hang_and_fall() {
         let mut grid = Grid::empty();
         let mut garbage_system = GarbageSystem::default();
-        grid[0] = Component::Normal(Block::default());
+        grid[0] = idle_block();
 
         // hang state setting
-        grid.assert_state(0, |s| s.is_idle());
-        if let Some(state) = grid.block_state_mut(0) {
-            state.to_hang();
+        assert_state(&grid, 0, |s| matches!(s, BlockState::Idle));
+        if let Component::Block { state, .. } = &mut grid[0] {
+            *state = BlockState::Hang { counter: 0 };
         } else {
-            assert!(false);
+            panic!("no block at 0");
         }
-        grid.assert_state(0, |s| s.is_hang());
-        grid.assert_state(0, |s| s.hang_started());
+        assert_state(&grid, 0, |s| matches!(s, BlockState::Hang { counter: 0 }));
 
         // hang state updating
-        for i in 0..HANG_TIME {
+        // NOTE(Skytrias): fall is resolved below, otherwhise the block would already fall on the last frame
+        for _ in 0..HANG_TIME {
             grid.update_components();
 
-            grid.block_resolve_fall();
             grid.block_resolve_hang(&mut garbage_system);
         }
 
         // is in fall state now
-        grid.assert_state(0, |s| s.is_fall());
+        assert_state(&grid, 0, |s| matches!(s, BlockState::Fall));
 
         // check if fall succeeds to swap components around
-        assert!(grid[0].is_block());
-        assert!(grid[GRID_WIDTH].is_empty());
+        assert!(is_block(&grid[0]));
+        assert!(is_empty(&grid[GRID_WIDTH]));
         grid.update_components();
         grid.block_resolve_fall();
-        assert!(grid[0].is_empty());
-        assert!(grid[GRID_WIDTH].is_block());
+        assert!(is_empty(&grid[0]));
+        assert!(is_block(&grid[GRID_WIDTH]));
+    }
+
+    /// swaps pressed during a swap wait in the buffer, smart swaps are dropped on cursor moves
+    #[test]
+    fn swap_buffer() {
+        use miniquad::KeyCode;
+
+        let mut grid = Grid::empty();
+        let mut input = Input::headless();
+        let mut cursor = Cursor::default();
+        let bottom = GRID_HEIGHT - 2;
+        cursor.position = i2(0, bottom as i32);
+        grid[bottom * GRID_WIDTH] = idle_block();
+        grid[GRID_TOTAL - GRID_WIDTH] = idle_block();
+        grid[GRID_TOTAL - GRID_WIDTH + 1] = idle_block();
+
+        let press = |input: &mut Input, cursor: &mut Cursor, grid: &mut Grid| {
+            input.down_event(KeyCode::S);
+            cursor.update(input, &mut grid.components);
+            input.up_event(KeyCode::S);
+            input.update_end();
+        };
+
+        press(&mut input, &mut cursor, &mut grid);
+        assert_state(&grid, bottom * GRID_WIDTH, |s| {
+            matches!(s, BlockState::Swap { .. })
+        });
+
+        // second swap is pressed while the first is still swapping
+        press(&mut input, &mut cursor, &mut grid);
+        assert!(cursor.buffered_swap.is_some());
+
+        for _ in 0..=SWAP_TIME {
+            grid.update_components();
+            grid.block_resolve_swap();
+            grid.block_detect_hang();
+            cursor.update(&input, &mut grid.components);
+        }
+        assert!(cursor.buffered_swap.is_none());
+        assert_state(&grid, bottom * GRID_WIDTH + 1, |s| {
+            matches!(s, BlockState::Swap { .. })
+        });
+
+        input.controls.players[0].smart_swap = true;
+        press(&mut input, &mut cursor, &mut grid);
+        assert!(cursor.buffered_swap.is_some());
+        cursor.position.x += 1;
+        cursor.update(&input, &mut grid.components);
+        assert!(cursor.buffered_swap.is_none());
     }
 
     /// check if swap to idle works in the wanted frame times
@@ -1469,39 +1236,42 @@ mod tests {
     fn block_swap() {
         let mut grid = Grid::empty();
         let mut cursor = Cursor::default();
-        cursor.position = v2(0., 0.);
+        cursor.position = i2(0, 0);
 
-        assert!(grid[0].is_empty());
-        assert!(grid[1].is_empty());
-        cursor.swap_blocks(&mut grid);
-        assert!(grid[0].is_empty());
-        assert!(grid[1].is_empty());
+        assert!(is_empty(&grid[0]));
+        assert!(is_empty(&grid[1]));
+        cursor.swap_blocks(&mut grid.components);
+        assert!(is_empty(&grid[0]));
+        assert!(is_empty(&grid[1]));
 
-        grid[0] = Component::Normal(Block::default());
+        grid[0] = idle_block();
 
-        assert!(grid[0].is_block());
-        assert!(grid[1].is_empty());
-        cursor.swap_blocks(&mut grid);
-        assert!(grid[0].is_block());
-        assert!(grid[1].is_empty());
-        grid.assert_state(0, |s| s.is_swap());
+        assert!(is_block(&grid[0]));
+        assert!(is_empty(&grid[1]));
+        cursor.swap_blocks(&mut grid.components);
+        assert!(is_block(&grid[0]));
+        assert!(is_empty(&grid[1]));
+        assert_state(&grid, 0, |s| matches!(s, BlockState::Swap { .. }));
 
         // swap state updating
-        for i in 0..SWAP_TIME {
+        for _ in 0..SWAP_TIME {
             grid.update_components();
         }
 
-        grid.assert_state(0, |s| s.swap_finished());
+        assert_state(&grid, 0, |s| match s {
+            BlockState::Swap { counter, .. } => *counter >= SWAP_TIME - 1,
+            _ => false,
+        });
         grid.update_components();
 
         // NOTE(Skytrias): matches the resolve / detect grid.update
         grid.block_resolve_swap();
         grid.block_detect_hang();
 
-        assert!(grid[0].is_empty());
-        assert!(grid[1].is_block());
+        assert!(is_empty(&grid[0]));
+        assert!(is_block(&grid[1]));
 
         // block should transition to hang immediatly
-        grid.assert_state(1, |s| s.is_hang());
+        assert_state(&grid, 1, |s| matches!(s, BlockState::Hang { .. }));
     }
 }
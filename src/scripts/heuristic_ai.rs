@@ -0,0 +1,495 @@
+use crate::helpers::*;
+use crate::scripts::{
+    Ai, BlockState, Component, CursorState, GarbageSystem, Grid, Patterns, Plan, Threat,
+};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// cpu that reacts to the current field with fixed priorities
+/// prepares chains for clearing garbage, digs towards idle garbage, then plays swap patterns, solves simple 3s / 4s near idle garbage and removes peaks
+/// raising and the allowed peak height depend on the threat the grid is in
+#[derive(Clone)]
+pub struct HeuristicAi {
+    /// swap patterns tried before the simple 3s / 4s, shared by all copies
+    pub patterns: Arc<Patterns>,
+}
+
+impl Default for HeuristicAi {
+    fn default() -> Self {
+        Self {
+            patterns: Arc::new(Patterns::builtin()),
+        }
+    }
+}
+
+impl Ai for HeuristicAi {
+    fn plan(&mut self, grid: &Grid, garbage_system: &GarbageSystem) -> Plan {
+        let mut plan = Plan::default();
+
+        // raise only while nothing threatens the grid
+        let threat = Threat::evaluate(grid, garbage_system);
+        plan.raise = threat.should_raise();
+
+        // garbage has priority, prepare chains for clearing garbage and dig towards idle garbage
+        if let Some(y) = garbage_system.lowest_clear(grid) {
+            // prefer spawn chain preperation
+            self.solve_spawn_vertically(grid, &mut plan, y + 1);
+        } else if let Some(y) = garbage_system.lowest_idle(grid) {
+            // solve garbage vertically by 3 if possible, else remove peaks,
+            let max = y + 2;
+
+            // NOTE(Skytrias): hardcoded offsets
+            self.solve_horizontally(grid, &mut plan, 3, y + 1, y + 2);
+            self.solve_vertically(grid, &mut plan, 3, 0..GRID_WIDTH, y..max + 3);
+
+            if let Some(start_index) = self.nearest_hole(grid, y + 1) {
+                for x in 1..GRID_WIDTH {
+                    let i = (y + 1) * GRID_WIDTH + x;
+
+                    if let Component::Empty { .. } = &grid[i] {
+                        continue;
+                    }
+
+                    if let Component::Block { .. } = &grid[i] {
+                        let goal = if grid.cursor.position.x <= x as i32 {
+                            i - 1
+                        } else {
+                            i
+                        }
+                        .to_i2();
+
+                        plan.states.push_back(CursorState::MoveTransport {
+                            counter: 0,
+                            reached: false,
+                            swap_end: true,
+                            start: start_index.to_i2(),
+                            goal,
+                        });
+                        break;
+                    }
+                }
+            } else {
+                self.solve_normally(grid, &mut plan);
+            }
+
+        // else panic and do usual stuff?
+        /*
+        	*/
+        } else {
+            // solve normally
+            self.solve_patterns(grid, &mut plan);
+            //self.solve_horizontally(grid, &mut plan, 3, 0, GRID_HEIGHT - 1);
+            //self.solve_vertically(grid, &mut plan, 4, 0..GRID_WIDTH, 0..GRID_HEIGHT);
+            //self.solve_vertically(grid, &mut plan, 3, 0..GRID_WIDTH, 0..GRID_HEIGHT);
+        }
+
+        // flatten peaks once nothing else is planned, less peaks are allowed in danger
+        if plan.states.is_empty() {
+            if let Some(x) = self.detect_difference(grid, threat.peak_tolerance()) {
+                self.remove_peaks(grid, &mut plan, x);
+                let x = (x as i32 - 1).max(0) as usize;
+                self.solve_vertically(grid, &mut plan, 3, x..x + 1, 0..GRID_HEIGHT);
+            }
+        }
+
+        plan
+    }
+
+    fn name(&self) -> &'static str {
+        "heuristic"
+    }
+
+    fn clone_box(&self) -> Box<dyn Ai> {
+        Box::new(self.clone())
+    }
+}
+
+impl HeuristicAi {
+    /// swap patterns first, transports blocks into 3s / 4s if none matched
+    fn solve_normally(&self, grid: &Grid, plan: &mut Plan) {
+        if !self.solve_patterns(grid, plan) {
+            self.solve_horizontally(grid, plan, 3, 0, GRID_HEIGHT - 1);
+            self.solve_vertically(grid, plan, 4, 0..GRID_WIDTH, 0..GRID_HEIGHT);
+            self.solve_vertically(grid, plan, 3, 0..GRID_WIDTH, 0..GRID_HEIGHT);
+        }
+    }
+
+    /// plans the swap of the highest priority pattern found, rated by its priority, returns false if none matched
+    fn solve_patterns(&self, grid: &Grid, plan: &mut Plan) -> bool {
+        match self.patterns.find(grid) {
+            Some((pattern, goal)) => {
+                plan.score = Some(pattern.priority);
+                plan.states
+                    .push_back(CursorState::MoveSwap { counter: 0, goal });
+                true
+            }
+
+            None => false,
+        }
+    }
+
+    fn solve_vertically(
+        &self,
+        grid: &Grid,
+        plan: &mut Plan,
+        goal_amount: usize,
+        x_range: Range<usize>,
+        y_range: Range<usize>,
+    ) {
+        debug_assert!(goal_amount != 0);
+
+        // TODO(Skytrias): reevaluate smallest distance so that states might be reset in favor
+
+        let mut smallest_distance: Option<i32> = None;
+        let mut searched_indexes = Vec::new();
+
+        for y in y_range.start..y_range.end - goal_amount {
+            'skip: for x in x_range.clone() {
+                // TODO(Skytrias): modify if more / less vframes exist
+                for &vframe in &[3, 4, 5, 6, 7] {
+                    // TODO(Skytrias): not great
+                    let mut indexes = Vec::new();
+                    let mut goal_counter = 0;
+                    let mut sum_distance = 0;
+
+                    for y_off in 0..goal_amount {
+                        'inner: for x_off in 0..GRID_WIDTH {
+                            let i = (y + y_off) * GRID_WIDTH + x;
+                            let j = (y + y_off) * GRID_WIDTH + x_off;
+
+                            if let Component::Empty { .. } = &grid[i] {
+                                break 'skip;
+                            }
+
+                            if let Component::Block { block, state } = &grid[j] {
+                                if let BlockState::Idle = state {
+                                    if block.vframe == vframe {
+                                        goal_counter += 1;
+                                        let distance = x as i32 - x_off as i32;
+                                        sum_distance += distance.abs();
+
+                                        if i != j {
+                                            indexes.push((i, distance));
+                                        }
+
+                                        break 'inner;
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    if goal_counter == goal_amount {
+                        if let Some(distance) = smallest_distance.as_mut() {
+                            if *distance > sum_distance {
+                                *distance = sum_distance;
+                                searched_indexes = indexes;
+                            }
+                        } else {
+                            smallest_distance = Some(sum_distance);
+                            searched_indexes = indexes;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(smallest) = smallest_distance {
+            for (start_index, distance) in searched_indexes {
+                // move to index, dependant on the direction move further
+                let goal = if distance > 0 {
+                    start_index as i32 - smallest
+                } else {
+                    start_index as i32 - (smallest + 1)
+                };
+
+                plan.states.push_back(CursorState::MoveTransport {
+                    counter: 0,
+                    reached: false,
+                    swap_end: true,
+                    start: start_index.to_i2(),
+                    goal: goal.to_i2(),
+                });
+            }
+        }
+    }
+
+    // checks wether an x path has any empty or clearing block
+    fn path_has_empty(&self, grid: &Grid, x_start: usize, x_end: usize, y_axis: usize) -> bool {
+        let min = x_start.min(x_end);
+        let max = x_start.max(x_end);
+
+        for x in min..max {
+            let i = y_axis * GRID_WIDTH + x;
+
+            if let Component::Empty { .. } = &grid[i] {
+                if i < GRID_TOTAL - GRID_WIDTH {
+                    if let Component::Empty { .. } = &grid[i + GRID_WIDTH] {
+                        return true;
+                    }
+                }
+            }
+
+            if let Component::Block { state, .. } = &grid[i] {
+                if let BlockState::Clear { .. } = state {
+                    return true;
+                } else {
+                    return false;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn solve_spawn_vertically(&self, grid: &Grid, plan: &mut Plan, y_start: usize) {
+        // TODO(Skytrias): reevaluate smallest distance so that states might be reset in favor
+
+        // only allow 3x1 pairs
+        'search: for x in 0..GRID_WIDTH {
+            let i = y_start * GRID_WIDTH + x;
+            let mut indexes = Vec::new();
+            let mut goal_counter = 0;
+
+            if let Component::Block { block, state } = &grid[i] {
+                if let BlockState::Spawned = state {
+                    // search for others nearby on the next y positions below
+                    for y_off in 1..3 {
+                        // if below is already the same, skip
+                        if let Component::Block {
+                            block: below_block, ..
+                        } = &grid[(y_start + y_off) * GRID_WIDTH + x]
+                        {
+                            if below_block.vframe == block.vframe {
+                                continue;
+                            }
+                        }
+
+                        'next: for x_off in 0..GRID_WIDTH {
+                            let j = (y_start + y_off) * GRID_WIDTH + x_off;
+
+                            if let Component::Block {
+                                block: below_block, ..
+                            } = &grid[j]
+                            {
+                                if self.path_has_empty(grid, x_off, x, y_start + y_off) {
+                                    continue;
+                                }
+
+                                if below_block.vframe == block.vframe {
+                                    goal_counter += 1;
+                                    indexes.push(((y_start + y_off) * GRID_WIDTH + x, j));
+
+                                    if goal_counter > 1 {
+                                        for (start, end) in &indexes {
+                                            let goal = {
+                                                let pos = end.to_i2();
+
+                                                if pos.x < grid.cursor.position.x {
+                                                    pos
+                                                } else {
+                                                    i2(pos.x, pos.y)
+                                                }
+                                            };
+
+                                            plan.states.push_back(CursorState::MoveTransport {
+                                                counter: 0,
+                                                reached: false,
+                                                swap_end: false,
+                                                start: start.to_i2(),
+                                                goal,
+                                            });
+                                        }
+
+                                        break 'search;
+                                    }
+
+                                    break 'next;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn solve_horizontally(
+        &self,
+        grid: &Grid,
+        plan: &mut Plan,
+        goal_amount: usize,
+        y_start: usize,
+        y_end: usize,
+    ) {
+        debug_assert!(goal_amount != 0);
+
+        // TODO(Skytrias): reevaluate smallest distance so that states might be reset in favor
+
+        let mut smallest_distance: Option<i32> = None;
+        let mut searched_indexes = Vec::new();
+
+        for &vframe in &[3, 4, 5, 6, 7] {
+            for y in y_start..y_end {
+                for x in 0..GRID_WIDTH - goal_amount {
+                    // TODO(Skytrias): skip if x axis doesnt contain goal_amount of vframe
+
+                    // TODO(Skytrias): not great
+                    let mut indexes = Vec::new();
+                    let mut goal_counter = 0;
+                    let mut sum_distance = 0;
+
+                    for x_off in 0..GRID_WIDTH {
+                        let i = y * GRID_WIDTH + x;
+                        let j = y * GRID_WIDTH + x_off;
+
+                        // TODO(Skytrias): skip if somewhere below has a empty
+                        // TODO(Skytrias): skip if x axis has clear?
+
+                        if let Component::Block { block, state } = &grid[j] {
+                            if let BlockState::Idle = state {
+                                if block.vframe == vframe {
+                                    goal_counter += 1;
+                                    let distance = x as i32 - x_off as i32;
+                                    sum_distance += distance.abs();
+
+                                    // skip if i is the same as j, the start of the search
+                                    if i != j {
+                                        indexes.push((i, distance));
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // if goal reached, check if smaller
+                    if goal_counter == goal_amount {
+                        if let Some(distance) = smallest_distance.as_mut() {
+                            if *distance > sum_distance {
+                                *distance = sum_distance;
+                                searched_indexes = indexes;
+                            }
+                        } else {
+                            smallest_distance = Some(sum_distance);
+                            searched_indexes = indexes;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(smallest) = smallest_distance {
+            for (start_index, distance) in searched_indexes {
+                // move to index, dependant on the direction move further
+                let goal = if distance > 0 {
+                    start_index as i32 - smallest
+                } else {
+                    start_index as i32 - (smallest + 1)
+                };
+
+                plan.states.push_back(CursorState::MoveTransport {
+                    counter: 0,
+                    reached: false,
+                    swap_end: false,
+                    start: start_index.to_i2(),
+                    goal: goal.to_i2(),
+                });
+            }
+        }
+    }
+
+    // TODO(Skytrias): TAKE AVERAGE
+    /// detects the highest and lowest block y peak and returns true if the difference equals the height_difference
+    fn detect_difference(&self, grid: &Grid, height_difference: i32) -> Option<usize> {
+        let mut y_min = GRID_HEIGHT;
+        let mut y_max = 0;
+        let mut x_axis = 0;
+
+        for x in 0..GRID_WIDTH {
+            for y in 0..GRID_HEIGHT {
+                let i = y * GRID_WIDTH + x;
+
+                if let Component::Block { .. } = &grid[i] {
+                    y_max = y_max.max(y);
+
+                    if y_min > y {
+                        y_min = y;
+                        x_axis = x;
+                    }
+
+                    break;
+                }
+            }
+        }
+
+        if (y_min as i32 - y_max as i32).abs() > height_difference {
+            Some(x_axis)
+        } else {
+            None
+        }
+    }
+
+    fn remove_peaks(&self, grid: &Grid, plan: &mut Plan, x_axis: usize) {
+        for y in 0..GRID_HEIGHT - 1 {
+            let i = y * GRID_WIDTH + x_axis;
+
+            // TODO(Skytrias): move based on cursor pos
+            if let Component::Block { .. } = &grid[i] {
+                let goal = if grid.cursor.position.x <= x_axis as i32 {
+                    i
+                } else {
+                    i
+                }
+                .to_i2();
+
+                plan.states
+                    .push_back(CursorState::MoveSwap { counter: 0, goal });
+                return;
+            }
+        }
+    }
+
+    fn nearest_hole(&self, grid: &Grid, y_axis: usize) -> Option<usize> {
+        // TODO(Skytrias): if y == GRID_HEIGHT - 1
+
+        for x in 0..GRID_WIDTH {
+            let i = y_axis * GRID_WIDTH + x;
+
+            if let Component::Empty { .. } = &grid[i] {
+                if i < GRID_TOTAL - GRID_WIDTH {
+                    if let Component::Empty { .. } = &grid[i + GRID_WIDTH] {
+                        return Some(i);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a nearly empty grid asks for a raise, a high peak gets cursor states to remove it
+    #[test]
+    fn heuristic_plan() {
+        let garbage_system = GarbageSystem::default();
+        let mut grid = Grid::empty();
+        assert!(HeuristicAi::default().plan(&grid, &garbage_system).raise);
+
+        for y in 2..GRID_HEIGHT {
+            grid[y * GRID_WIDTH] = Component::spawn(Some(3 + y as u32 % 5));
+        }
+
+        for x in 1..GRID_WIDTH {
+            grid[GRID_TOTAL - GRID_WIDTH + x] = Component::spawn(Some(3 + x as u32 % 5));
+        }
+
+        assert!(!HeuristicAi::default()
+            .plan(&grid, &garbage_system)
+            .states
+            .is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::engine::Sprites;
+use crate::helpers::*;
+use crate::scripts::{
+    draw_plan, Ai, CursorState, GarbageSystem, Grid, HeuristicAi, Plan, SearchAi,
+};
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// frames till the hint is searched again
+const HINT_INTERVAL: u32 = 30;
+
+/// training aid that shows a human player the swaps leading to the next clear or chain
+/// only reads the grid, the cursor of the player is never moved
+#[derive(Default)]
+pub struct Hint {
+    /// toggled per player
+    pub enabled: bool,
+
+    /// swaps the player could do, drawn like a cpu plan
+    pub states: VecDeque<CursorState>,
+
+    /// search score of the hinted swaps
+    pub score: Option<i32>,
+
+    /// frames left till the next search
+    counter: u32,
+
+    /// searches on its own thread, started on the first update
+    worker: Option<HintWorker>,
+}
+
+impl Clone for Hint {
+    /// copies of the grid share no worker, a copied hint starts its own once it is updated
+    fn clone(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            states: self.states.clone(),
+            score: self.score,
+            counter: self.counter,
+            worker: None,
+        }
+    }
+}
+
+/// long lived search thread of a hint, fed with grid copies over a channel
+struct HintWorker {
+    /// grids to search, the thread ends once this is dropped
+    requests: Sender<(Grid, GarbageSystem)>,
+
+    /// plans of the finished searches
+    results: Receiver<Plan>,
+
+    /// true while a sent grid was not answered yet
+    searching: bool,
+}
+
+impl HintWorker {
+    fn spawn() -> Self {
+        let (requests, receiver) = channel::<(Grid, GarbageSystem)>();
+        let (sender, results) = channel();
+
+        std::thread::spawn(move || {
+            // fallback once the search finds nothing
+            let mut heuristic = HeuristicAi::default();
+
+            for (grid, garbage_system) in receiver {
+                let plan = Hint::search(&mut heuristic, &grid, &garbage_system);
+
+                if sender.send(plan).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            requests,
+            results,
+            searching: false,
+        }
+    }
+}
+
+impl Hint {
+    /// turns the hint on / off, hints are searched right away once turned on
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.states.clear();
+        self.score = None;
+        self.counter = 0;
+    }
+
+    /// sends a grid copy to the worker every interval, so the game never waits for the search
+    /// the hinted states are replaced once the search finished
+    pub fn update(&mut self, grid: &Grid, garbage_system: &GarbageSystem) {
+        if !self.enabled {
+            return;
+        }
+
+        let worker = self.worker.get_or_insert_with(HintWorker::spawn);
+
+        if let Ok(plan) = worker.results.try_recv() {
+            self.states = plan.states;
+            self.score = plan.score;
+            worker.searching = false;
+        }
+
+        if self.counter > 0 {
+            self.counter -= 1;
+            return;
+        }
+
+        // previous search still running, send the next grid once it is done
+        if worker.searching {
+            return;
+        }
+
+        self.counter = HINT_INTERVAL;
+        worker.searching = worker
+            .requests
+            .send((grid.clone(), garbage_system.clone()))
+            .is_ok();
+    }
+
+    /// the best swap sequence of the search cpu within its node budget,
+    /// or the states of the heuristic cpu if no sequence clears anything
+    pub fn search(
+        heuristic: &mut HeuristicAi,
+        grid: &Grid,
+        garbage_system: &GarbageSystem,
+    ) -> Plan {
+        let plan = SearchAi::default().sequence(grid, garbage_system);
+
+        if plan.states.is_empty() {
+            heuristic.plan(grid, garbage_system)
+        } else {
+            plan
+        }
+    }
+
+    /// draws the hinted swaps starting at the player cursor
+    pub fn draw(&self, sprites: &mut Sprites, grid: &Grid, offset: V2) {
+        if self.enabled {
+            draw_plan(
+                sprites,
+                offset + v2(0., grid.cursor.y_offset),
+                grid.cursor.position,
+                &self.states,
+                self.score,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripts::search_ai::tests::chain_grid;
+
+    /// the worker of the hint finds the swap that starts a chain
+    #[test]
+    fn hint_swap() {
+        let grid = chain_grid();
+        let bottom = GRID_HEIGHT - 2;
+
+        let garbage_system = GarbageSystem::default();
+        let mut hint = Hint::default();
+        hint.update(&grid, &garbage_system);
+        assert!(hint.states.is_empty());
+        assert!(hint.worker.is_none());
+
+        // wait for the worker to answer
+        hint.toggle();
+        for _ in 0..5000 {
+            hint.update(&grid, &garbage_system);
+
+            if !hint.states.is_empty() {
+                break;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        assert!(matches!(
+            hint.states.front(),
+            Some(CursorState::MoveSwap { goal, .. }) if *goal == i2(2, bottom as i32)
+        ));
+    }
+}
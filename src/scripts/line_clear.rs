@@ -0,0 +1,143 @@
+use crate::engine::*;
+use crate::helpers::*;
+use crate::scripts::{update_single, GarbageSystem, Grid, ModeState};
+
+/// frames the game runs per second, used to convert the time limit
+const FRAMES_PER_SECOND: u32 = 60;
+
+/// single player mode, clear the goal amount of rows worth of blocks before the time runs out
+pub struct LineClear {
+    /// amount of rows worth of blocks that need to be cleared
+    pub goal_lines: u32,
+
+    /// frame time the player has to reach the goal
+    pub time_limit: u32,
+
+    /// frames passed since the start
+    pub counter: u32,
+
+    pub state: ModeState,
+}
+
+impl LineClear {
+    /// creates the mode with the line goal and the time limit in seconds, huge limits saturate
+    pub fn new(goal_lines: u32, seconds: u32) -> Self {
+        Self {
+            goal_lines,
+            time_limit: seconds.saturating_mul(FRAMES_PER_SECOND),
+            counter: 0,
+            state: ModeState::Playing,
+        }
+    }
+
+    /// resets the grid and the timer
+    pub fn start(&mut self, grid: &mut Grid, garbage_system: &mut GarbageSystem) {
+        garbage_system.list.clear();
+        grid.reset();
+        self.counter = 0;
+        self.state = ModeState::Playing;
+    }
+
+    /// rows worth of blocks cleared so far, tracked by block_resolve_clear
+    pub fn cleared_lines(&self, grid: &Grid) -> u32 {
+        grid.cleared_blocks / GRID_WIDTH as u32
+    }
+
+    /// updates the grid and timer while playing, retries on confirm once finished
+    pub fn update(&mut self, input: &Input, grid: &mut Grid, garbage_system: &mut GarbageSystem) {
+        if self.state != ModeState::Playing {
+            // retry on confirm, the single grid always belongs to the first player
+            if input.control_pressed(0, Control::Start) {
+                self.start(grid, garbage_system);
+            }
+
+            return;
+        }
+
+        update_single(input, grid, garbage_system);
+        self.counter += 1;
+
+        if self.cleared_lines(grid) >= self.goal_lines {
+            self.state = ModeState::Won;
+        } else if grid.topped_out || self.counter >= self.time_limit {
+            self.state = ModeState::Lost;
+        }
+    }
+
+    /// draws the remaining lines and seconds next to the grid
+    pub fn draw(&self, sprites: &mut Sprites, grid: &Grid, offset: V2) {
+        let text_position = offset + v2(GRID_WIDTH as f32 + 1., 0.) * ATLAS_SPACING;
+        let lines_left = self.goal_lines.saturating_sub(self.cleared_lines(grid));
+        let seconds_left =
+            (self.time_limit - self.counter.min(self.time_limit)) / FRAMES_PER_SECOND;
+
+        sprites.text(Text {
+            content: &format!("lines {}", lines_left),
+            position: text_position,
+            ..Default::default()
+        });
+
+        sprites.text(Text {
+            content: &format!("time {}", seconds_left),
+            position: text_position + v2(0., ATLAS_TILE),
+            ..Default::default()
+        });
+
+        let state_text = match self.state {
+            ModeState::Playing => return,
+            ModeState::Won => "clear",
+            ModeState::Lost => "failed",
+        };
+
+        sprites.text(Text {
+            content: state_text,
+            position: text_position + v2(0., ATLAS_TILE * 2.),
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// reaching the line goal wins
+    #[test]
+    fn line_clear_won() {
+        let input = Input::headless();
+        let mut grid = Grid::empty();
+        let mut garbage_system = GarbageSystem::default();
+        let mut line_clear = LineClear::new(2, 10);
+
+        grid.cleared_blocks = GRID_WIDTH as u32 * 2 - 1;
+        line_clear.update(&input, &mut grid, &mut garbage_system);
+        assert_eq!(line_clear.state, ModeState::Playing);
+
+        grid.cleared_blocks += 1;
+        line_clear.update(&input, &mut grid, &mut garbage_system);
+        assert_eq!(line_clear.state, ModeState::Won);
+    }
+
+    /// running out of time loses
+    #[test]
+    fn line_clear_lost() {
+        let input = Input::headless();
+        let mut grid = Grid::empty();
+        let mut garbage_system = GarbageSystem::default();
+        let mut line_clear = LineClear::new(2, 1);
+
+        for _ in 0..FRAMES_PER_SECOND - 1 {
+            line_clear.update(&input, &mut grid, &mut garbage_system);
+        }
+        assert_eq!(line_clear.state, ModeState::Playing);
+
+        line_clear.update(&input, &mut grid, &mut garbage_system);
+        assert_eq!(line_clear.state, ModeState::Lost);
+    }
+
+    /// time limits beyond u32 frames saturate instead of overflowing
+    #[test]
+    fn line_clear_time_limit() {
+        assert_eq!(LineClear::new(1, u32::MAX).time_limit, u32::MAX);
+    }
+}
@@ -15,3 +15,63 @@ pub use self::block::*;
 
 mod garbage;
 pub use self::garbage::*;
+
+mod mode;
+pub use self::mode::*;
+
+mod stage_clear;
+pub use self::stage_clear::*;
+
+mod line_clear;
+pub use self::line_clear::*;
+
+mod field;
+pub use self::field::*;
+
+mod editor;
+pub use self::editor::*;
+
+mod sandbox;
+pub use self::sandbox::*;
+
+mod timeline;
+pub use self::timeline::*;
+
+mod ai;
+pub use self::ai::*;
+
+mod heuristic_ai;
+pub use self::heuristic_ai::*;
+
+mod search_ai;
+pub use self::search_ai::*;
+
+mod difficulty;
+pub use self::difficulty::*;
+
+mod threat;
+pub use self::threat::*;
+
+mod tournament;
+pub use self::tournament::*;
+
+mod external_ai;
+pub use self::external_ai::*;
+
+mod env;
+pub use self::env::*;
+
+mod pattern;
+pub use self::pattern::*;
+
+mod hint;
+pub use self::hint::*;
+
+mod rebind;
+pub use self::rebind::*;
+
+mod pointer;
+pub use self::pointer::*;
+
+mod name_entry;
+pub use self::name_entry::*;
@@ -0,0 +1,275 @@
+use crate::engine::{Control, Input};
+use crate::helpers::*;
+use crate::scripts::{
+    ComboVariant, Difficulty, Editor, Field, GarbageSystem, Grid, LineClear, Sandbox, StageClear,
+};
+
+/// states a single player mode can be in
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ModeState {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// progress of a single player mode, rewinded together with the grids
+#[derive(Copy, Clone, PartialEq)]
+pub enum ModeProgress {
+    StageClear { current: usize, state: ModeState },
+    LineClear { counter: u32, state: ModeState },
+}
+
+/// settings of a versus match
+pub struct VersusSettings {
+    /// field both grids start with
+    pub field: Option<Field>,
+
+    /// name of the ai controlling the second grid, see ai_from_name
+    pub cpu: String,
+
+    pub difficulty: Difficulty,
+
+    /// humans playing, the second player takes the cpu grid
+    pub players: usize,
+}
+
+impl Default for VersusSettings {
+    fn default() -> Self {
+        Self {
+            field: None,
+            cpu: "heuristic".to_string(),
+            difficulty: Difficulty::default(),
+            players: 1,
+        }
+    }
+}
+
+/// game modes the app can run in, chosen by the command line arguments
+pub enum Mode {
+    /// multiple grids sending garbage to each other, the second grid is played by a cpu or bot
+    Versus(Box<VersusSettings>),
+
+    /// single grid, clear all blocks above the rising clear line
+    StageClear(StageClear),
+
+    /// single grid, clear a number of rows worth of blocks before the time runs out
+    LineClear(LineClear),
+
+    /// single grid that is edited with the mouse and saved as a field file
+    Editor(Editor),
+
+    /// versus with tools to spawn garbage, freeze rising, toggle the ai and step frames
+    Sandbox(Sandbox),
+}
+
+impl Mode {
+    /// picks the mode from the command line arguments, i.e. "everpuzzle stage 3", "everpuzzle lines 10 120",
+    /// "everpuzzle editor puzzles/field.txt", "everpuzzle versus hard puzzles/field.txt", "everpuzzle bot python3 bot.py"
+    /// or "everpuzzle sandbox"
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let args: Vec<String> = args.collect();
+        let arg = |i: usize| args.get(i).map(String::as_str);
+        let number = |i: usize| arg(i).and_then(|arg| arg.parse::<u32>().ok());
+
+        // field file at the argument, a field that fails to load is skipped
+        let field = |i: usize| {
+            arg(i).and_then(|path| match Field::load(path) {
+                Ok(field) => Some(field),
+
+                Err(e) => {
+                    eprintln!("MODE: failed to load field {}: {}", path, e);
+                    None
+                }
+            })
+        };
+
+        match arg(0).unwrap_or("") {
+            "stage" => {
+                Mode::StageClear(StageClear::new(number(1).unwrap_or(1).max(1) as usize - 1))
+            }
+            "lines" => Mode::LineClear(LineClear::new(
+                number(1).unwrap_or(10).max(1),
+                number(2).unwrap_or(120),
+            )),
+            "editor" => Mode::Editor(Editor::new(
+                arg(1).unwrap_or("puzzles/field.txt").to_string(),
+            )),
+            "sandbox" => Mode::Sandbox(Sandbox::default()),
+            "" | "versus" => {
+                // optional difficulty before the field path
+                let difficulty = arg(1).and_then(Difficulty::from_name);
+
+                Mode::Versus(Box::new(VersusSettings {
+                    field: field(if difficulty.is_some() { 2 } else { 1 }),
+                    difficulty: difficulty.unwrap_or_default(),
+                    ..Default::default()
+                }))
+            }
+
+            // two humans, the keyboard is split if the second player has no keys bound
+            "2p" => Mode::Versus(Box::new(VersusSettings {
+                field: field(1),
+                players: 2,
+                ..Default::default()
+            })),
+
+            // the rest of the arguments are the bot command
+            "bot" => Mode::Versus(Box::new(VersusSettings {
+                cpu: format!("bot={}", args[1..].join(" ")),
+                ..Default::default()
+            })),
+
+            name => {
+                eprintln!("MODE: unknown mode {:?}, starting versus", name);
+                Mode::Versus(Box::default())
+            }
+        }
+    }
+}
+
+impl Mode {
+    /// offsets the grids of the mode are drawn at, single grid modes draw at the origin
+    pub fn offsets(&self) -> &'static [V2] {
+        match self {
+            Mode::Versus(..) | Mode::Sandbox(_) => &VERSUS_OFFSETS,
+            _ => &VERSUS_OFFSETS[..1],
+        }
+    }
+
+    /// true while the grids are played, false in the editor and once stage / line clear are won or lost
+    pub fn playing(&self) -> bool {
+        match self {
+            Mode::StageClear(stage) => stage.state == ModeState::Playing,
+            Mode::LineClear(line_clear) => line_clear.state == ModeState::Playing,
+            Mode::Editor(_) => false,
+            _ => true,
+        }
+    }
+
+    /// progress of the single player modes, none for the other modes
+    pub fn progress(&self) -> Option<ModeProgress> {
+        match self {
+            Mode::StageClear(stage) => Some(ModeProgress::StageClear {
+                current: stage.current,
+                state: stage.state,
+            }),
+
+            Mode::LineClear(line_clear) => Some(ModeProgress::LineClear {
+                counter: line_clear.counter,
+                state: line_clear.state,
+            }),
+
+            _ => None,
+        }
+    }
+
+    /// restores the progress of a snapshot, ignored if it belongs to another mode
+    pub fn set_progress(&mut self, progress: ModeProgress) {
+        match (self, progress) {
+            (Mode::StageClear(stage), ModeProgress::StageClear { current, state }) => {
+                stage.current = current;
+                stage.state = state;
+            }
+
+            (Mode::LineClear(line_clear), ModeProgress::LineClear { counter, state }) => {
+                line_clear.counter = counter;
+                line_clear.state = state;
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// updates a single grid without any opponents, including manual raise input
+pub fn update_single(input: &Input, grid: &mut Grid, garbage_system: &mut GarbageSystem) {
+    if input.control_down(grid.cursor.player, Control::Raise) {
+        grid.push_raise = true;
+    }
+
+    grid.update(input, garbage_system);
+    garbage_system.update(grid);
+    grid.push_update(garbage_system);
+}
+
+/// updates all grids and sends garbage to the other grids when a combo / chain arrives
+pub fn update_versus(input: &Input, grids: &mut [Grid], garbage_system: &mut GarbageSystem) {
+    // reset grid
+    // TODO(Skytrias): garbage not resetting
+    // NOTE(Skytrias): only the first player resets, start on other gamepads joins them instead
+    if input.control_pressed(0, Control::Start) {
+        for grid in grids.iter_mut() {
+            grid.reset();
+        }
+    }
+
+    // manual raise of each player
+    for grid in grids.iter_mut().filter(|grid| !grid.cursor.ai) {
+        if input.control_down(grid.cursor.player, Control::Raise) {
+            grid.push_raise = true;
+        }
+    }
+
+    // let each grid know about the largest chain running on any other grid
+    let chains: Vec<u32> = grids.iter().map(|grid| grid.chain_in_progress()).collect();
+    for (i, grid) in grids.iter_mut().enumerate() {
+        grid.opponent_chain = chains
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| i != j)
+            .map(|(_, &chain)| chain)
+            .max()
+            .unwrap_or(0);
+    }
+
+    // update all grids
+    let len = grids.len();
+    for i in 0..len {
+        grids[i].update(input, garbage_system);
+
+        // spawns garbage on other grids if a new combo arrives
+        for combo_index in 0..grids[i].combo_highlight.list.len() {
+            // TODO(Skytrias): creates copies, might be bad cuz of performance
+            if !grids[i].combo_highlight.list[combo_index].sent {
+                let combo_data = grids[i].combo_highlight.list[combo_index];
+
+                for (j, grid) in grids.iter_mut().enumerate() {
+                    // skip on the same grid as the goal
+                    if i == j {
+                        continue;
+                    }
+
+                    match combo_data.variant {
+                        ComboVariant::Combo => {
+                            grid.gen_1d_garbage(garbage_system, combo_data.size as usize)
+                        }
+                        ComboVariant::Chain => {
+                            grid.gen_2d_garbage(garbage_system, combo_data.size as usize)
+                        }
+                    }
+                }
+
+                // statistics of the sender
+                let grid = &mut grids[i];
+                match combo_data.variant {
+                    ComboVariant::Combo => grid.garbage_sent += combo_data.size,
+
+                    ComboVariant::Chain => {
+                        grid.garbage_sent += combo_data.size * GRID_WIDTH as u32;
+
+                        // each link of a chain arrives with the next size
+                        match grid.chains.last_mut() {
+                            Some(last) if combo_data.size > 2 => *last = combo_data.size,
+                            _ => grid.chains.push(combo_data.size),
+                        }
+                    }
+                }
+
+                grid.combo_highlight.list[combo_index].sent = true;
+            }
+        }
+
+        garbage_system.update(&mut grids[i]);
+        grids[i].push_update(garbage_system);
+    }
+}
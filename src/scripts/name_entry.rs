@@ -0,0 +1,113 @@
+use crate::engine::*;
+use crate::helpers::*;
+use miniquad::KeyCode;
+
+/// frames the caret stays visible / hidden
+const CARET_BLINK: u32 = 30;
+
+/// result of a finished name entry
+#[derive(Clone, Debug, PartialEq)]
+pub enum NameEvent {
+    Confirmed(String),
+    Cancelled,
+}
+
+/// single line text field for names, paths and profiles
+///
+/// typed characters are appended till the max length, backspace removes the last one with auto repeat
+/// enter confirms and escape cancels
+#[derive(Clone, Debug)]
+pub struct NameEntry {
+    pub text: String,
+    pub max_length: usize,
+
+    /// frames since the entry opened, blinks the caret
+    counter: u32,
+}
+
+impl NameEntry {
+    pub fn new(text: &str, max_length: usize) -> Self {
+        Self {
+            text: text.chars().take(max_length).collect(),
+            max_length,
+            counter: 0,
+        }
+    }
+
+    /// appends the typed text, returns an event once confirmed or cancelled
+    pub fn update(&mut self, input: &Input) -> Option<NameEvent> {
+        self.counter += 1;
+
+        for c in input.text.chars() {
+            if self.text.chars().count() < self.max_length && (c.is_ascii_graphic() || c == ' ') {
+                self.text.push(c);
+            }
+        }
+
+        if let Some(frames) = input.key_down_frames(KeyCode::Backspace) {
+            if input.bindings(0).repeats(frames) {
+                self.text.pop();
+            }
+        }
+
+        if input.key_pressed(KeyCode::Escape) {
+            return Some(NameEvent::Cancelled);
+        }
+
+        if input.key_pressed(KeyCode::Enter) && !self.text.trim().is_empty() {
+            return Some(NameEvent::Confirmed(self.text.trim().to_string()));
+        }
+
+        None
+    }
+
+    /// draws the text with a blinking caret behind it
+    pub fn draw(&self, sprites: &mut Sprites, position: V2, scale: V2) {
+        let mut content = self.text.clone();
+        if (self.counter / CARET_BLINK) % 2 == 0 {
+            content.push('_');
+        }
+
+        sprites.text(Text {
+            content: &content,
+            position,
+            scale,
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// typed text is appended till the limit, backspace removes, enter confirms
+    #[test]
+    fn name_entry_typing() {
+        let mut input = Input::headless();
+        let mut entry = NameEntry::new("ab", 4);
+
+        input.char_event('c');
+        input.char_event('\u{8}');
+        input.char_event('d');
+        input.char_event('e');
+        assert_eq!(entry.update(&input), None);
+        assert_eq!(entry.text, "abcd");
+        input.update_end();
+        assert!(input.text.is_empty());
+
+        input.down_event(KeyCode::Backspace);
+        entry.update(&input);
+        assert_eq!(entry.text, "abc");
+        input.update_end();
+        entry.update(&input);
+        assert_eq!(entry.text, "abc");
+        input.up_event(KeyCode::Backspace);
+
+        input.down_event(KeyCode::Enter);
+        assert_eq!(
+            entry.update(&input),
+            Some(NameEvent::Confirmed("abc".to_string()))
+        );
+    }
+}
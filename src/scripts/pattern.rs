@@ -0,0 +1,275 @@
+use crate::helpers::*;
+use crate::scripts::{BlockState, Component, Grid, FIRST_VFRAME};
+
+/// single cell of a pattern rectangle
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PatternCell {
+    /// matches anything
+    Any,
+
+    /// has to be empty
+    Empty,
+
+    /// idle block of the vframe
+    Color(u32),
+
+    /// idle block whose color is shared by all cells of the same wildcard
+    Wildcard(u8),
+}
+
+/// rectangle of cells that is searched for in the grid and the swap that completes it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub name: String,
+
+    /// higher priorities are searched first
+    pub priority: i32,
+
+    /// swap position relative to the top left of the rectangle
+    pub swap: I2,
+
+    pub width: usize,
+    pub height: usize,
+
+    /// cells in row order
+    pub cells: Vec<PatternCell>,
+}
+
+impl Pattern {
+    /// true if the pattern fits with its top left at the position, wildcards have to be distinct colors
+    pub fn matches(&self, grid: &Grid, position: I2) -> bool {
+        let mut wildcards: Vec<(u8, u32)> = Vec::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = (position.y as usize + y) * GRID_WIDTH + position.x as usize + x;
+
+                let vframe = match &grid[i] {
+                    Component::Block {
+                        block,
+                        state: BlockState::Idle,
+                    } => Some(block.vframe),
+                    _ => None,
+                };
+
+                let fits = match self.cells[y * self.width + x] {
+                    PatternCell::Any => true,
+                    PatternCell::Empty => matches!(grid[i], Component::Empty { .. }),
+                    PatternCell::Color(color) => vframe == Some(color),
+
+                    PatternCell::Wildcard(letter) => match vframe {
+                        Some(vframe) => match wildcards.iter().find(|(l, _)| *l == letter) {
+                            Some(&(_, bound)) => bound == vframe,
+
+                            None => {
+                                let taken = wildcards.iter().any(|&(_, v)| v == vframe);
+                                wildcards.push((letter, vframe));
+                                !taken
+                            }
+                        },
+
+                        None => false,
+                    },
+                };
+
+                if !fits {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// list of swap patterns sorted by priority, loaded from data/patterns.txt by default
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patterns {
+    pub list: Vec<Pattern>,
+}
+
+impl Patterns {
+    /// parses "pattern NAME PRIORITY X Y" headers each followed by the rows of the rectangle
+    /// . anything, _ empty, 1 - 5 colors and a - z color wildcards, # starts a comment
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut list: Vec<Pattern> = Vec::new();
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: &str| format!("line {}: {}", number + 1, message);
+
+            if line.starts_with("pattern") {
+                let words: Vec<&str> = line.split_whitespace().collect();
+
+                let number = |i: usize| {
+                    words
+                        .get(i)
+                        .and_then(|word| word.parse::<i32>().ok())
+                        .ok_or_else(|| error("expected \"pattern NAME PRIORITY X Y\""))
+                };
+
+                list.push(Pattern {
+                    name: words.get(1).unwrap_or(&"").to_string(),
+                    priority: number(2)?,
+                    swap: i2(number(3)?, number(4)?),
+                    width: 0,
+                    height: 0,
+                    cells: Vec::new(),
+                });
+                continue;
+            }
+
+            let pattern = list
+                .last_mut()
+                .ok_or_else(|| error("row outside of a pattern"))?;
+
+            if pattern.height > 0 && line.len() != pattern.width {
+                return Err(error("rows of a pattern need the same width"));
+            }
+
+            for c in line.chars() {
+                pattern.cells.push(match c {
+                    '.' => PatternCell::Any,
+                    '_' => PatternCell::Empty,
+                    '1'..='5' => PatternCell::Color(c as u32 - '1' as u32 + FIRST_VFRAME),
+                    'a'..='z' => PatternCell::Wildcard(c as u8),
+                    _ => return Err(error(&format!("unknown cell {:?}", c))),
+                });
+            }
+
+            pattern.width = line.len();
+            pattern.height += 1;
+        }
+
+        for pattern in list.iter() {
+            if pattern.height == 0 || pattern.width > GRID_WIDTH || pattern.height > GRID_HEIGHT - 1
+            {
+                return Err(format!("pattern {} has an invalid size", pattern.name));
+            }
+
+            if pattern.swap.x < 0
+                || pattern.swap.y < 0
+                || pattern.swap.x as usize >= pattern.width
+                || pattern.swap.y as usize >= pattern.height
+            {
+                return Err(format!(
+                    "pattern {} swaps outside of its rectangle",
+                    pattern.name
+                ));
+            }
+        }
+
+        list.sort_by_key(|pattern| std::cmp::Reverse(pattern.priority));
+        Ok(Self { list })
+    }
+
+    /// loads patterns from any file
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Patterns::parse(&content)
+    }
+
+    /// patterns shipped with the game
+    pub fn builtin() -> Self {
+        let data = load_file!("data/patterns.txt");
+
+        match Patterns::parse(&String::from_utf8_lossy(&data)) {
+            Ok(patterns) => patterns,
+
+            Err(e) => {
+                eprintln!("PATTERNS: data/patterns.txt {}", e);
+                Patterns::default()
+            }
+        }
+    }
+
+    /// swap position of the highest priority pattern in the grid, the nearest to the cursor on ties
+    /// the spawned bottom row is never part of a pattern
+    pub fn find(&self, grid: &Grid) -> Option<(&Pattern, I2)> {
+        let mut best: Option<(&Pattern, I2, i32)> = None;
+
+        for pattern in self.list.iter() {
+            if best.map_or(false, |(found, _, _)| found.priority > pattern.priority) {
+                break;
+            }
+
+            for y in 0..GRID_HEIGHT - pattern.height {
+                for x in 0..=GRID_WIDTH - pattern.width {
+                    let position = i2(x as i32, y as i32);
+                    let swap = position + pattern.swap;
+
+                    // the right block of the swap has to lie in the grid
+                    if swap.x >= GRID_WIDTH as i32 - 1 || !pattern.matches(grid, position) {
+                        continue;
+                    }
+
+                    let distance = (swap.x - grid.cursor.position.x).abs()
+                        + (swap.y - grid.cursor.position.y).abs();
+
+                    if best.map_or(true, |(_, _, nearest)| distance < nearest) {
+                        best = Some((pattern, swap, distance));
+                    }
+                }
+            }
+        }
+
+        best.map(|(pattern, swap, _)| (pattern, swap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripts::search_ai::tests::chain_grid;
+
+    /// the builtin file parses and finds the chain setup before any 3
+    #[test]
+    fn patterns_find() {
+        let patterns = Patterns::builtin();
+        assert!(!patterns.list.is_empty());
+        assert!(patterns
+            .list
+            .windows(2)
+            .all(|w| w[0].priority >= w[1].priority));
+
+        let grid = chain_grid();
+        let bottom = GRID_HEIGHT - 2;
+
+        let (pattern, swap) = patterns.find(&grid).unwrap();
+        assert_eq!(pattern.name, "chain_2_right");
+        assert_eq!(swap, i2(2, bottom as i32));
+    }
+
+    /// wildcards share colors, different letters differ
+    #[test]
+    fn patterns_parse() {
+        let patterns = Patterns::parse("pattern row 1 0 0\nab_1\n").unwrap();
+        assert_eq!(
+            patterns.list[0].cells,
+            vec![
+                PatternCell::Wildcard(b'a'),
+                PatternCell::Wildcard(b'b'),
+                PatternCell::Empty,
+                PatternCell::Color(FIRST_VFRAME),
+            ]
+        );
+
+        assert!(Patterns::parse("a.\n").is_err());
+        assert!(Patterns::parse("pattern x 1 0 0\na.\na\n").is_err());
+        assert!(Patterns::parse("pattern x 1 2 0\na.\n").is_err());
+        assert!(Patterns::parse("pattern x 1 0 0\na?\n").is_err());
+
+        let mut grid = Grid::empty();
+        grid[0] = Component::spawn(Some(3));
+        grid[1] = Component::spawn(Some(3));
+        let pattern = Patterns::parse("pattern x 1 0 0\nab\n").unwrap().list[0].clone();
+        assert!(!pattern.matches(&grid, i2(0, 0)));
+        grid[1] = Component::spawn(Some(4));
+        assert!(pattern.matches(&grid, i2(0, 0)));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::engine::Input;
+use crate::helpers::*;
+use crate::scripts::{BlockState, Component, Grid};
+
+/// grid position under the point, includes the rise of the stack, the spawned bottom row is excluded
+/// points are in screen pixels, the same space sprites are drawn in since the projection spans the screen size
+pub fn grid_position(grid: &Grid, point: V2, offset: V2) -> Option<I2> {
+    let position = (point - offset - v2(0., grid.cursor.y_offset)) / ATLAS_SPACING;
+    let (x, y) = (position.x.floor() as i32, position.y.floor() as i32);
+
+    if x >= 0 && x < GRID_WIDTH as i32 && y >= 0 && y < GRID_HEIGHT as i32 - 1 {
+        Some(i2(x, y))
+    } else {
+        None
+    }
+}
+
+/// drag to swap control of the player grids with the mouse or touch
+///
+/// grabbing a block and dragging it horizontally swaps it one column at a time towards the pointer
+/// each swap waits for the last one to finish, tapping without dragging raises the stack
+#[derive(Default)]
+pub struct Pointer {
+    /// only updates while enabled, turned on by M or the first touch
+    pub enabled: bool,
+
+    /// index of the grid the grab started on, any grid not played by a cpu can be grabbed
+    grid: usize,
+
+    /// grid position of the grabbed block, follows the block with each swap
+    grabbed: Option<I2>,
+
+    /// raised rows at the time of the grab, each raise moves the grabbed block up
+    raised_rows: u32,
+
+    /// true once the grabbed block was swapped, releasing without a swap is a tap
+    dragged: bool,
+}
+
+impl Pointer {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.grabbed = None;
+    }
+
+    /// grabs a block of the grid under the pointer, the offsets are the drawn position of each grid
+    pub fn update(&mut self, input: &Input, grids: &mut [Grid], offsets: &[V2]) {
+        if !self.enabled {
+            return;
+        }
+
+        let mouse = &input.mouse;
+
+        if mouse.left_pressed {
+            self.grabbed = None;
+
+            for (i, (grid, &offset)) in grids.iter().zip(offsets.iter()).enumerate() {
+                if grid.cursor.ai {
+                    continue;
+                }
+
+                if let Some(position) = grid_position(grid, mouse.position, offset) {
+                    self.grid = i;
+                    self.grabbed = Some(position);
+                    self.raised_rows = grid.raised_rows;
+                    self.dragged = false;
+                }
+            }
+        }
+
+        let (grid, offset) = match (grids.get_mut(self.grid), offsets.get(self.grid)) {
+            (Some(grid), Some(&offset)) => (grid, offset),
+            _ => return,
+        };
+
+        let mut grabbed = match self.grabbed {
+            Some(grabbed) if !grid.cursor.ai => grabbed,
+
+            _ => {
+                self.grabbed = None;
+                return;
+            }
+        };
+
+        // the stack rose under the grab
+        grabbed.y -= (grid.raised_rows - self.raised_rows) as i32;
+        self.raised_rows = grid.raised_rows;
+
+        if grabbed.y < 0 {
+            self.grabbed = None;
+            return;
+        }
+
+        if mouse.left_released {
+            if !self.dragged && grid_position(grid, mouse.position, offset) == Some(grabbed) {
+                grid.push_raise = true;
+            }
+
+            self.grabbed = None;
+            return;
+        }
+
+        let column = ((mouse.position.x - offset.x) / ATLAS_SPACING.x).floor() as i32;
+        let step = (column.max(0).min(GRID_WIDTH as i32 - 1) - grabbed.x).signum();
+        let idle = matches!(
+            grid[grabbed.to_index()],
+            Component::Block {
+                state: BlockState::Idle,
+                ..
+            }
+        );
+
+        // the cursor swaps the grabbed block with its neighbor
+        if step != 0 && idle {
+            grid.cursor.position = i2(grabbed.x.min(grabbed.x + step), grabbed.y);
+
+            if grid.cursor.can_swap(&grid.components) {
+                grid.cursor.swap_blocks(&mut grid.components);
+                grabbed.x += step;
+                self.dragged = true;
+            }
+        }
+
+        self.grabbed = Some(grabbed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// dragging swaps the grabbed block of the grid under the pointer step by step, tapping raises
+    #[test]
+    fn pointer_drag() {
+        let mut grids = vec![Grid::empty(), Grid::empty()];
+        let mut input = Input::headless();
+        let mut pointer = Pointer::default();
+        pointer.toggle();
+
+        let bottom = GRID_HEIGHT - 2;
+        let offset = VERSUS_OFFSETS[1];
+        let center =
+            |x: usize, y: usize| offset + (v2(x as f32, y as f32) + v2(0.5, 0.5)) * ATLAS_SPACING;
+        for x in 0..GRID_WIDTH {
+            grids[1][bottom * GRID_WIDTH + x] = Component::spawn(Some(3 + x as u32 % 2));
+            grids[1][GRID_TOTAL - GRID_WIDTH + x] = Component::spawn(Some(5));
+        }
+
+        assert_eq!(
+            grid_position(&grids[1], center(1, bottom), offset),
+            Some(i2(1, bottom as i32))
+        );
+        assert_eq!(
+            grid_position(&grids[1], center(1, bottom + 1), offset),
+            None
+        );
+        assert_eq!(grid_position(&grids[1], v2(10., 10.), offset), None);
+
+        input.mouse.position = center(1, bottom);
+        input.mouse.left_pressed = true;
+        pointer.update(&input, &mut grids, &VERSUS_OFFSETS);
+        input.mouse.left_pressed = false;
+
+        // drag two columns to the right, the second swap waits for the first
+        input.mouse.position = center(3, bottom);
+        pointer.update(&input, &mut grids, &VERSUS_OFFSETS);
+        assert!(matches!(
+            grids[1][bottom * GRID_WIDTH + 1],
+            Component::Block {
+                state: BlockState::Swap { .. },
+                ..
+            }
+        ));
+        assert_eq!(pointer.grabbed, Some(i2(2, bottom as i32)));
+
+        pointer.update(&input, &mut grids, &VERSUS_OFFSETS);
+        assert_eq!(pointer.grabbed, Some(i2(2, bottom as i32)));
+
+        input.mouse.left_released = true;
+        pointer.update(&input, &mut grids, &VERSUS_OFFSETS);
+        assert!(pointer.grabbed.is_none());
+        assert!(!grids[1].push_raise);
+
+        // tap
+        input.mouse.left_released = false;
+        input.mouse.position = center(4, bottom);
+        input.mouse.left_pressed = true;
+        pointer.update(&input, &mut grids, &VERSUS_OFFSETS);
+        input.mouse.left_pressed = false;
+        input.mouse.left_released = true;
+        pointer.update(&input, &mut grids, &VERSUS_OFFSETS);
+        assert!(grids[1].push_raise);
+        assert!(!grids[0].push_raise);
+
+        // cpu grids can't be grabbed
+        grids[1].set_ai(Some(Box::new(crate::scripts::HeuristicAi::default())));
+        input.mouse.left_released = false;
+        input.mouse.left_pressed = true;
+        pointer.update(&input, &mut grids, &VERSUS_OFFSETS);
+        assert!(pointer.grabbed.is_none());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::engine::*;
+use crate::helpers::*;
+use gilrs::Button;
+use miniquad::KeyCode;
+
+/// screen to rebind the controls of each player, the game waits while it is open
+///
+/// Up / Down select the control, Left / Right the player
+/// Enter waits for the next key or button, a key replaces the keys and a button the buttons of the control
+/// Backspace removes all bindings of the control
+#[derive(Default)]
+pub struct RebindScreen {
+    /// index of the player whose bindings are shown
+    pub player: usize,
+
+    /// index of the selected control in Control::ALL
+    pub selected: usize,
+
+    /// true while waiting for the next key or button
+    pub listening: bool,
+}
+
+impl RebindScreen {
+    pub fn update(&mut self, input: &mut Input) {
+        let control = Control::ALL[self.selected];
+
+        if self.listening {
+            if let Some(binding) = input.pressed_binding() {
+                input.controls.players[self.player].rebind(control, binding);
+                self.listening = false;
+            }
+
+            return;
+        }
+
+        if input.kb_pressed(KeyCode::Up, Button::DPadUp) {
+            self.selected = (self.selected + Control::ALL.len() - 1) % Control::ALL.len();
+        }
+
+        if input.kb_pressed(KeyCode::Down, Button::DPadDown) {
+            self.selected = (self.selected + 1) % Control::ALL.len();
+        }
+
+        if input.kb_pressed(KeyCode::Left, Button::DPadLeft) {
+            self.player = self.player.saturating_sub(1);
+        }
+
+        if input.kb_pressed(KeyCode::Right, Button::DPadRight) {
+            self.player = (self.player + 1).min(MAX_PLAYERS - 1);
+        }
+
+        // players without bindings start with the defaults
+        while input.controls.players.len() <= self.player {
+            input.controls.players.push(Bindings::buttons());
+        }
+
+        if input.kb_pressed(KeyCode::Enter, Button::South) {
+            self.listening = true;
+        }
+
+        if input.key_pressed(KeyCode::Backspace) {
+            input.controls.players[self.player]
+                .controls
+                .insert(control, Vec::new());
+        }
+    }
+
+    /// draws the bindings of the player, the selected control is underlaid
+    pub fn draw(&self, sprites: &mut Sprites, input: &Input) {
+        let scale = V2::broadcast(0.5);
+        let step = ATLAS_TILE * scale.y * 1.5;
+        let bindings = input.controls.player(self.player);
+
+        sprites.text(Text {
+            content: &format!("player {}", self.player + 1),
+            position: v2(step, step),
+            scale,
+            ..Default::default()
+        });
+
+        for (i, &control) in Control::ALL.iter().enumerate() {
+            let position = v2(step, (i + 3) as f32 * step);
+
+            let mut line = format!("{:<8}", control.name());
+            if self.listening && i == self.selected {
+                line.push_str("press a key or button");
+            } else {
+                for binding in bindings.get(control) {
+                    line.push_str(&format!("{}  ", binding));
+                }
+            }
+
+            sprites.text(Text {
+                content: &line,
+                position,
+                scale,
+                ..Default::default()
+            });
+
+            if i == self.selected {
+                sprites.push(Sprite {
+                    position: position - v2(step / 2., 0.),
+                    hframe: 1,
+                    scale: v2(
+                        sprites.text_width(&line, scale) / ATLAS_TILE + scale.x,
+                        scale.y,
+                    ),
+                    depth: 0.95,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::engine::*;
+use crate::helpers::*;
+use crate::scripts::{update_versus, Ai, CursorState, GarbageSystem, Grid, HeuristicAi, SearchAi};
+use miniquad::KeyCode;
+
+/// versus with debugging tools, every tool works on the target grid
+///
+/// garbage width with Minus / Equal, height with [ / ], column with Comma / Period, G spawns it
+/// F freezes rising, C cycles through player / heuristic / search cpu, D cycles the cpu difficulty, T switches the target grid
+/// left click moves the target cursor to the mouse, the ai also swaps there
+pub struct Sandbox {
+    /// index of the grid the tools are used on
+    pub target: usize,
+
+    /// width of spawned 1d garbage, 2d garbage always spans the grid width
+    pub garbage_width: usize,
+
+    /// height of spawned garbage, 2d if larger than 1
+    pub garbage_height: usize,
+
+    /// x position of spawned 1d garbage
+    pub garbage_column: usize,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            target: 0,
+            garbage_width: 3,
+            garbage_height: 1,
+            garbage_column: 0,
+        }
+    }
+}
+
+impl Sandbox {
+    /// actual width of the garbage that will be spawned
+    fn width(&self) -> usize {
+        if self.garbage_height > 1 {
+            GRID_WIDTH
+        } else {
+            self.garbage_width
+        }
+    }
+
+    /// applies the tools to the target grid and updates all grids
+    pub fn update(
+        &mut self,
+        input: &Input,
+        grids: &mut [Grid],
+        garbage_system: &mut GarbageSystem,
+        offsets: &[V2],
+    ) {
+        if input.key_pressed(KeyCode::T) {
+            self.target = (self.target + 1) % grids.len();
+        }
+
+        if input.key_pressed(KeyCode::Minus) {
+            self.garbage_width = (self.garbage_width - 1).max(3);
+        }
+
+        if input.key_pressed(KeyCode::Equal) {
+            self.garbage_width = (self.garbage_width + 1).min(GRID_WIDTH);
+        }
+
+        if input.key_pressed(KeyCode::LeftBracket) {
+            self.garbage_height = (self.garbage_height - 1).max(1);
+        }
+
+        if input.key_pressed(KeyCode::RightBracket) {
+            self.garbage_height = (self.garbage_height + 1).min(GRID_HEIGHT / 2);
+        }
+
+        if input.key_pressed(KeyCode::Comma) {
+            self.garbage_column = self.garbage_column.saturating_sub(1);
+        }
+
+        if input.key_pressed(KeyCode::Period) {
+            self.garbage_column += 1;
+        }
+
+        self.garbage_column = self.garbage_column.min(GRID_WIDTH - self.width());
+
+        let grid = &mut grids[self.target];
+
+        if input.key_pressed(KeyCode::G) {
+            grid.spawn_garbage(
+                garbage_system,
+                i2(self.garbage_column as i32, 0),
+                self.width(),
+                self.garbage_height,
+            );
+        }
+
+        if input.key_pressed(KeyCode::D) {
+            grid.cursor.difficulty = grid.cursor.difficulty.next();
+        }
+
+        if input.key_pressed(KeyCode::F) {
+            grid.push_frozen = !grid.push_frozen;
+        }
+
+        if input.key_pressed(KeyCode::C) {
+            // cycles through player, heuristic cpu and search cpu
+            let next: Option<Box<dyn Ai>> = match grid.ai.as_ref().map(|ai| ai.name()) {
+                None => Some(Box::new(HeuristicAi::default())),
+                Some("heuristic") => Some(Box::new(SearchAi::interactive())),
+                _ => None,
+            };
+
+            grid.set_ai(next);
+        }
+
+        // move the cursor to the clicked block and swap there
+        if input.mouse.left_pressed {
+            let position = (input.mouse.position - offsets[self.target]) / ATLAS_SPACING;
+            let goal = i2(
+                position.x.floor() as i32,
+                (position.y + grid.push_amount / ATLAS_TILE).floor() as i32,
+            );
+
+            if goal.x >= 0
+                && goal.x < GRID_WIDTH as i32 - 1
+                && goal.y >= 0
+                && goal.y < GRID_HEIGHT as i32 - 1
+            {
+                if grid.cursor.ai {
+                    grid.cursor
+                        .states
+                        .push_back(CursorState::MoveSwap { counter: 0, goal });
+                } else {
+                    grid.cursor.position = goal;
+                }
+            }
+        }
+
+        update_versus(input, grids, garbage_system);
+    }
+
+    /// draws the tool settings next to the target grid
+    pub fn draw(&self, sprites: &mut Sprites, grids: &[Grid], offsets: &[V2]) {
+        let grid = &grids[self.target];
+        let position = offsets[self.target] + v2(GRID_WIDTH as f32, 0.) * ATLAS_SPACING;
+        let scale = V2::broadcast(0.5);
+        let step = ATLAS_TILE * scale.y;
+
+        let lines = [
+            format!("target {}", self.target + 1),
+            format!("garbage {}x{}", self.width(), self.garbage_height),
+            format!("column {}", self.garbage_column),
+            if grid.push_frozen { "frozen" } else { "rising" }.to_string(),
+            grid.ai
+                .as_ref()
+                .map_or("player", |ai| ai.name())
+                .to_string(),
+            grid.cursor.difficulty.name.to_string(),
+        ];
+
+        for (i, line) in lines.iter().enumerate() {
+            sprites.text(Text {
+                content: line,
+                position: position + v2(step, i as f32 * step),
+                scale,
+                ..Default::default()
+            });
+        }
+    }
+}
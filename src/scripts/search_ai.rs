@@ -0,0 +1,385 @@
+use crate::helpers::*;
+use crate::scripts::{
+    Ai, BlockState, ComboVariant, Component, CursorState, GarbageState, GarbageSystem, Grid, Plan,
+    Threat,
+};
+use std::time::{Duration, Instant};
+
+/// amount of best sequences that get expanded at each depth
+const BEAM_WIDTH: usize = 6;
+/// maximum amount of frames a copied grid is simulated till it settles after a swap
+const SETTLE_LIMIT: u32 = 600;
+/// default amount of swapped grids that are settled for a single decision
+const DEFAULT_NODES: usize = 250;
+/// time an interactive search may take for a single decision, so a slow machine never stalls the window
+const INTERACTIVE_TIME_LIMIT: Duration = Duration::from_millis(8);
+
+/// score weights, chains outweigh everything else
+const CHAIN_SCORE: i32 = 1000;
+const CLEAR_SCORE: i32 = 10;
+const GARBAGE_SCORE: i32 = 5;
+const SWAP_COST: i32 = 2;
+/// extra weight for clearing garbage and cost per stack row, both scaled by the danger the grid is in
+const DANGER_GARBAGE_SCORE: f32 = 20.;
+const DANGER_HEIGHT_COST: f32 = 30.;
+
+/// copied grid after a sequence of swaps that all settled
+#[derive(Clone)]
+struct Node {
+    grid: Grid,
+    garbage_system: GarbageSystem,
+    swaps: Vec<I2>,
+
+    /// largest chain that happened in the sequence
+    chain: u32,
+
+    /// total score of the sequence, higher is better
+    score: i32,
+}
+
+/// cpu that simulates swap sequences on copied grids with a beam search, as deep as the cursor difficulty allows
+/// lets every swap settle before the next one, so chains are built up first and triggered by the last swap
+/// only the first swap of the best sequence is played, the rest is searched again on the next plan
+#[derive(Clone)]
+pub struct SearchAi {
+    /// amount of swapped grids settled for a single decision, the best sequence found so far is used once it runs out
+    /// the same grid always results in the same plan
+    pub nodes: usize,
+
+    /// optional safety cap for interactive play, the plan depends on the machine speed once it is hit
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for SearchAi {
+    fn default() -> Self {
+        Self {
+            nodes: DEFAULT_NODES,
+            time_limit: None,
+        }
+    }
+}
+
+impl Ai for SearchAi {
+    fn plan(&mut self, grid: &Grid, garbage_system: &GarbageSystem) -> Plan {
+        // raise only while nothing threatens the grid, same as the heuristic cpu
+        let mut plan = Plan {
+            raise: Threat::evaluate(grid, garbage_system).should_raise(),
+            ..Default::default()
+        };
+
+        if let Some(node) = self.best_sequence(grid, garbage_system) {
+            plan.score = Some(node.score);
+            plan.states.push_back(CursorState::MoveSwap {
+                counter: 0,
+                goal: node.swaps[0],
+            });
+        }
+
+        plan
+    }
+
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    fn clone_box(&self) -> Box<dyn Ai> {
+        Box::new(self.clone())
+    }
+}
+
+impl SearchAi {
+    /// search with a time limit on top of the node budget, used while a window is open
+    pub fn interactive() -> Self {
+        Self {
+            time_limit: Some(INTERACTIVE_TIME_LIMIT),
+            ..Default::default()
+        }
+    }
+
+    /// returns the first swap of the best sequence that clears anything
+    pub fn search(&self, grid: &Grid, garbage_system: &GarbageSystem) -> Option<I2> {
+        self.best_sequence(grid, garbage_system)
+            .map(|node| node.swaps[0])
+    }
+
+    /// the whole best sequence as swap states, rated by its score
+    pub fn sequence(&self, grid: &Grid, garbage_system: &GarbageSystem) -> Plan {
+        let mut plan = Plan::default();
+
+        if let Some(node) = self.best_sequence(grid, garbage_system) {
+            plan.score = Some(node.score);
+            plan.states.extend(
+                node.swaps
+                    .iter()
+                    .map(|&goal| CursorState::MoveSwap { counter: 0, goal }),
+            );
+        }
+
+        plan
+    }
+
+    /// beam search over swap sequences, keeps the best scoring one that cleared anything
+    fn best_sequence(&self, grid: &Grid, garbage_system: &GarbageSystem) -> Option<Node> {
+        let start = Instant::now();
+
+        let mut root_grid = grid.clone();
+        root_grid.ai = None;
+        root_grid.combo_highlight.clear();
+        let mut root = Node {
+            grid: root_grid,
+            garbage_system: garbage_system.clone(),
+            swaps: Vec::new(),
+            chain: 0,
+            score: 0,
+        };
+
+        // let running clears / falls finish first
+        if !settle(&mut root) {
+            return None;
+        }
+
+        let danger = Threat::evaluate(&root.grid, &root.garbage_system).danger();
+        let mut beam = vec![root.clone()];
+        let mut best: Option<Node> = None;
+        let mut nodes = 0;
+
+        'search: for _ in 0..grid.cursor.difficulty.depth {
+            let mut children = Vec::new();
+
+            for node in beam.iter() {
+                for goal in swap_candidates(&node.grid) {
+                    let timed_out = match self.time_limit {
+                        Some(limit) => start.elapsed() > limit,
+                        None => false,
+                    };
+
+                    if nodes >= self.nodes || timed_out {
+                        break 'search;
+                    }
+
+                    nodes += 1;
+
+                    let mut child = node.clone();
+                    child.grid.cursor.position = goal;
+                    child.grid.cursor.swap_blocks(&mut child.grid.components);
+                    child.swaps.push(goal);
+
+                    if !settle(&mut child) {
+                        continue;
+                    }
+
+                    child.score = score(&root, &child, danger);
+
+                    // only sequences that actually clear something are worth playing
+                    if child.grid.cleared_blocks > root.grid.cleared_blocks
+                        && best.as_ref().map_or(true, |b| child.score > b.score)
+                    {
+                        best = Some(child.clone());
+                    }
+
+                    children.push(child);
+                }
+            }
+
+            children.sort_by_key(|node| std::cmp::Reverse(node.score));
+            children.truncate(BEAM_WIDTH);
+            beam = children;
+        }
+
+        best
+    }
+}
+
+/// cursor positions where a swap would change the grid
+fn swap_candidates(grid: &Grid) -> Vec<I2> {
+    let mut candidates = Vec::new();
+
+    for y in 0..GRID_HEIGHT - 1 {
+        for x in 0..GRID_WIDTH - 1 {
+            let i = y * GRID_WIDTH + x;
+
+            let vframe = |component: &Component| match component {
+                Component::Block {
+                    block,
+                    state: BlockState::Idle,
+                } => Some(Some(block.vframe)),
+                Component::Empty { .. } => Some(None),
+                _ => None,
+            };
+
+            match (vframe(&grid[i]), vframe(&grid[i + 1])) {
+                (Some(left), Some(right)) if left != right => {
+                    candidates.push(i2(x as i32, y as i32))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    candidates
+}
+
+/// simulates the copied grid till all blocks and garbage are idle again, false if it never settled
+fn settle(node: &mut Node) -> bool {
+    for _ in 0..SETTLE_LIMIT {
+        node.grid.simulate(&mut node.garbage_system);
+        node.garbage_system.update(&mut node.grid);
+
+        for combo in node.grid.combo_highlight.list.iter() {
+            if let ComboVariant::Chain = combo.variant {
+                node.chain = node.chain.max(combo.size);
+            }
+        }
+
+        if is_settled(&node.grid, &node.garbage_system) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// true if no block or garbage of the grid is doing anything
+fn is_settled(grid: &Grid, garbage_system: &GarbageSystem) -> bool {
+    let blocks_idle = (0..GRID_TOTAL).all(|i| match &grid[i] {
+        Component::Block {
+            state: BlockState::Idle,
+            ..
+        } => {
+            // blocks above a freshly cleared block only start hanging the next frame
+            i + GRID_WIDTH >= GRID_TOTAL || !matches!(grid[i + GRID_WIDTH], Component::Empty { .. })
+        }
+        Component::Block { state, .. } => matches!(state, BlockState::Spawned),
+        _ => true,
+    });
+
+    blocks_idle
+        && garbage_system
+            .list
+            .iter()
+            .filter(|g| g.parent_id == grid.id)
+            .all(|g| matches!(g.state, GarbageState::Idle))
+}
+
+/// rates the sequence, chains count most, same colored neighbors reward setups that did not clear yet
+/// in danger clearing garbage and keeping the stack low matter more
+fn score(root: &Node, node: &Node, danger: f32) -> i32 {
+    let children = |grid: &Grid| {
+        grid.components
+            .iter()
+            .filter(|c| matches!(c, Component::Child(_)))
+            .count() as i32
+    };
+
+    let cleared = (node.grid.cleared_blocks - root.grid.cleared_blocks) as i32;
+    let garbage = children(&root.grid) - children(&node.grid);
+    let garbage_score = GARBAGE_SCORE + (DANGER_GARBAGE_SCORE * danger) as i32;
+    let height = Threat::evaluate(&node.grid, &node.garbage_system).stack_height as f32;
+
+    node.chain as i32 * CHAIN_SCORE
+        + cleared * CLEAR_SCORE
+        + garbage * garbage_score
+        + neighbors(&node.grid)
+        - node.swaps.len() as i32 * SWAP_COST
+        - (height * DANGER_HEIGHT_COST * danger) as i32
+}
+
+/// amount of horizontally or vertically touching blocks with the same color
+fn neighbors(grid: &Grid) -> i32 {
+    let vframe = |x: usize, y: usize| match &grid[y * GRID_WIDTH + x] {
+        Component::Block {
+            block,
+            state: BlockState::Idle,
+        } => Some(block.vframe),
+        _ => None,
+    };
+
+    let mut amount = 0;
+
+    for y in 0..GRID_HEIGHT - 1 {
+        for x in 0..GRID_WIDTH {
+            if let Some(v) = vframe(x, y) {
+                if x + 1 < GRID_WIDTH && vframe(x + 1, y) == Some(v) {
+                    amount += 1;
+                }
+
+                if y + 2 < GRID_HEIGHT && vframe(x, y + 1) == Some(v) {
+                    amount += 1;
+                }
+            }
+        }
+    }
+
+    amount
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// grid where swapping the 3rd and 4th block of the lowest row clears 3 reds, the greens above fall into a row of 3
+    /// shared by the cpu and hint tests
+    pub(crate) fn chain_grid() -> Grid {
+        let mut grid = Grid::empty();
+        let bottom = GRID_HEIGHT - 2;
+
+        // spawned row that holds everything up
+        for x in 0..GRID_WIDTH {
+            grid[(GRID_HEIGHT - 1) * GRID_WIDTH + x] = Component::Block {
+                block: Default::default(),
+                state: BlockState::Spawned,
+            };
+        }
+
+        let (red, green) = (3, 4);
+        let blocks = [
+            (0, bottom, red),
+            (1, bottom, red),
+            (2, bottom, green),
+            (3, bottom, red),
+            (1, bottom - 1, green),
+            (2, bottom - 1, green),
+        ];
+
+        for &(x, y, vframe) in blocks.iter() {
+            grid[y * GRID_WIDTH + x] = Component::spawn(Some(vframe));
+        }
+
+        grid
+    }
+
+    /// the swap that starts a 2 chain is found
+    #[test]
+    fn search_finds_chain() {
+        let garbage_system = GarbageSystem::default();
+        let grid = chain_grid();
+        let bottom = GRID_HEIGHT - 2;
+
+        let ai = SearchAi::default();
+        let node = ai.best_sequence(&grid, &garbage_system).unwrap();
+        assert_eq!(node.swaps[0], i2(2, bottom as i32));
+        assert_eq!(node.chain, 2);
+    }
+
+    /// the node budget limits the search, so the same grid always results in the same sequence
+    #[test]
+    fn search_deterministic() {
+        let garbage_system = GarbageSystem::default();
+        let mut rng = oorandom::Rand32::new(3);
+        let mut grid = Grid::new(0, 3, &Grid::gen_field(&mut rng, 5));
+        grid.cursor.difficulty.depth = 3;
+
+        let ai = SearchAi {
+            nodes: 100,
+            ..Default::default()
+        };
+        let sequence = |ai: &SearchAi| {
+            ai.best_sequence(&grid, &garbage_system)
+                .map(|node| (node.swaps, node.score))
+        };
+
+        let first = sequence(&ai);
+        assert!(first.is_some());
+        assert_eq!(first, sequence(&ai));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::engine::*;
+use crate::helpers::*;
+use crate::scripts::{update_single, GarbageSystem, Grid, ModeState};
+
+/// data of a single stage, loaded from data/stages.txt
+#[derive(Copy, Clone, Debug)]
+pub struct Stage {
+    /// frame time until the stack rises by a pixel
+    pub push_time: u32,
+
+    /// amount of rows the clear line starts below the stack
+    pub lines: u32,
+}
+
+/// single player mode, a stage is won once no block remains above the clear line
+pub struct StageClear {
+    /// all stages that can be played, increasing in speed
+    pub stages: Vec<Stage>,
+
+    /// index of the stage that is currently played
+    pub current: usize,
+
+    pub state: ModeState,
+}
+
+impl StageClear {
+    /// parses the stage list, each line holds "push_time lines", # starts a comment
+    pub fn parse(content: &str) -> Vec<Stage> {
+        content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| {
+                let mut numbers = line.split_whitespace().map(|n| n.parse::<u32>());
+
+                match (numbers.next(), numbers.next()) {
+                    (Some(Ok(push_time)), Some(Ok(lines))) => Some(Stage { push_time, lines }),
+                    _ => {
+                        eprintln!("STAGES: skipping invalid line {:?}", line);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// loads the stage data and starts at the wanted stage
+    pub fn new(start: usize) -> Self {
+        let data = load_file!("data/stages.txt");
+        let stages = StageClear::parse(&String::from_utf8_lossy(&data));
+        assert!(
+            !stages.is_empty(),
+            "STAGES: no stages found in data/stages.txt"
+        );
+
+        Self {
+            current: start.min(stages.len() - 1),
+            stages,
+            state: ModeState::Playing,
+        }
+    }
+
+    /// currently played stage data
+    pub fn stage(&self) -> Stage {
+        self.stages[self.current]
+    }
+
+    /// resets the grid and applies the current stage speed
+    pub fn start(&mut self, grid: &mut Grid, garbage_system: &mut GarbageSystem) {
+        garbage_system.list.clear();
+        grid.reset();
+        grid.push_time = self.stage().push_time;
+        self.state = ModeState::Playing;
+    }
+
+    /// y row of the clear line in the grid, can be below the grid
+    pub fn line_y(&self, grid: &Grid) -> i32 {
+        (GRID_HEIGHT - 1) as i32 + self.stage().lines as i32 - grid.raised_rows as i32
+    }
+
+    /// updates the grid while playing, detects win / loss, continues on confirm
+    pub fn update(&mut self, input: &Input, grid: &mut Grid, garbage_system: &mut GarbageSystem) {
+        if self.state != ModeState::Playing {
+            // next stage or retry on confirm, the single grid always belongs to the first player
+            if input.control_pressed(0, Control::Start) {
+                if self.state == ModeState::Won && self.current < self.stages.len() - 1 {
+                    self.current += 1;
+                }
+
+                self.start(grid, garbage_system);
+            }
+
+            return;
+        }
+
+        update_single(input, grid, garbage_system);
+
+        let line_y = self.line_y(grid);
+        if grid.topped_out {
+            self.state = ModeState::Lost;
+        } else if line_y < GRID_HEIGHT as i32 && grid.empty_above(line_y.max(0) as usize) {
+            self.state = ModeState::Won;
+        }
+    }
+
+    /// draws the clear line once it is inside the grid and the stage info
+    pub fn draw(&self, sprites: &mut Sprites, grid: &Grid, offset: V2) {
+        let line_y = self.line_y(grid);
+
+        if line_y < GRID_HEIGHT as i32 {
+            sprites.push(Sprite {
+                position: offset + v2(0., line_y as f32 * ATLAS_TILE - grid.push_amount - 2.),
+                hframe: 5,
+                scale: v2(GRID_WIDTH as f32, 4. / ATLAS_TILE),
+                depth: 0.2,
+                ..Default::default()
+            });
+        }
+
+        let text_position = offset + v2(GRID_WIDTH as f32 + 1., 0.) * ATLAS_SPACING;
+        sprites.text(Text {
+            content: &format!("stage {}", self.current + 1),
+            position: text_position,
+            ..Default::default()
+        });
+
+        let state_text = match self.state {
+            ModeState::Playing => return,
+            ModeState::Won => "clear",
+            ModeState::Lost => "failed",
+        };
+
+        sprites.text(Text {
+            content: state_text,
+            position: text_position + v2(0., ATLAS_TILE),
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripts::Component;
+
+    fn stage_clear(lines: u32) -> StageClear {
+        StageClear {
+            stages: vec![Stage {
+                push_time: 100,
+                lines,
+            }],
+            current: 0,
+            state: ModeState::Playing,
+        }
+    }
+
+    /// comments and invalid lines are skipped
+    #[test]
+    fn stage_parse() {
+        let stages = StageClear::parse("# push_time lines\n10 3\n\n5 2 # fast\n5\nfast 2\n");
+        assert_eq!(stages.len(), 2);
+        assert_eq!((stages[0].push_time, stages[0].lines), (10, 3));
+        assert_eq!((stages[1].push_time, stages[1].lines), (5, 2));
+    }
+
+    /// the clear line starts below the stack and moves up with each raised row
+    #[test]
+    fn stage_line_y() {
+        let stage = stage_clear(3);
+        let mut grid = Grid::empty();
+        assert_eq!(stage.line_y(&grid), GRID_HEIGHT as i32 + 2);
+
+        grid.raised_rows = 4;
+        assert_eq!(stage.line_y(&grid), GRID_HEIGHT as i32 - 2);
+    }
+
+    /// the stage is won once the line is inside the grid and nothing remains above it
+    #[test]
+    fn stage_won() {
+        let input = Input::headless();
+        let mut stage = stage_clear(1);
+        let mut grid = Grid::empty();
+        let mut garbage_system = GarbageSystem::default();
+
+        // line still below the grid
+        stage.update(&input, &mut grid, &mut garbage_system);
+        assert_eq!(stage.state, ModeState::Playing);
+
+        grid.raised_rows = 3;
+        grid[GRID_WIDTH * 8] = Component::spawn(Some(3));
+        stage.update(&input, &mut grid, &mut garbage_system);
+        assert_eq!(stage.state, ModeState::Playing);
+
+        grid[GRID_WIDTH * 8] = Component::spawn(None);
+        grid[GRID_WIDTH * 9] = Component::spawn(Some(3));
+        stage.update(&input, &mut grid, &mut garbage_system);
+        assert_eq!(stage.state, ModeState::Won);
+    }
+
+    /// topping out loses the stage
+    #[test]
+    fn stage_lost() {
+        let input = Input::headless();
+        let mut stage = stage_clear(1);
+        let mut grid = Grid::empty();
+        let mut garbage_system = GarbageSystem::default();
+
+        grid.topped_out = true;
+        stage.update(&input, &mut grid, &mut garbage_system);
+        assert_eq!(stage.state, ModeState::Lost);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::helpers::*;
+use crate::scripts::{BlockState, Component, GarbageState, GarbageSystem, Grid};
+
+/// expected height till which raising the stack is still safe
+const SAFE_HEIGHT: usize = 6;
+/// highest allowed difference between the tallest and lowest column while calm
+const CALM_PEAK_TOLERANCE: i32 = 5;
+/// highest allowed difference between the tallest and lowest column in danger
+const DANGER_PEAK_TOLERANCE: i32 = 2;
+
+/// danger a grid is in, used by the cpus to choose between attacking, digging and raising
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Threat {
+    /// rows from the bottom up to the highest block, the spawned row excluded
+    pub stack_height: usize,
+
+    /// rows that contain garbage above the highest block, garbage below it is part of the stack height
+    pub garbage_rows: usize,
+
+    /// wether any garbage of the grid is still falling or hanging
+    pub garbage_falling: bool,
+
+    /// largest chain currently running on an opponent grid, each link sends another row
+    pub opponent_chain: u32,
+}
+
+impl Threat {
+    /// looks at the grid, its garbage and the chains running on opponent grids
+    pub fn evaluate(grid: &Grid, garbage_system: &GarbageSystem) -> Self {
+        let mut threat = Threat {
+            opponent_chain: grid.opponent_chain,
+            ..Default::default()
+        };
+
+        for y in 0..GRID_HEIGHT - 1 {
+            let row = &grid.components[y * GRID_WIDTH..(y + 1) * GRID_WIDTH];
+
+            // rows below the highest block are already counted by the stack height
+            if threat.stack_height != 0 {
+                break;
+            }
+
+            let has_block = row.iter().any(|c| match c {
+                Component::Block { state, .. } => !matches!(state, BlockState::Clear { .. }),
+                _ => false,
+            });
+
+            if has_block {
+                threat.stack_height = GRID_HEIGHT - 1 - y;
+            } else if row.iter().any(|c| matches!(c, Component::Child(_))) {
+                threat.garbage_rows += 1;
+            }
+        }
+
+        threat.garbage_falling = garbage_system
+            .list
+            .iter()
+            .filter(|g| g.parent_id == grid.id)
+            .any(|g| matches!(g.state, GarbageState::Fall | GarbageState::Hang { .. }));
+
+        threat
+    }
+
+    /// rows the stack will reach once all garbage has landed and the opponent chain arrived
+    pub fn expected_height(&self) -> usize {
+        self.stack_height + self.garbage_rows + self.opponent_chain as usize
+    }
+
+    /// 0 while calm up to 1 when the grid is about to top out
+    pub fn danger(&self) -> f32 {
+        (self.expected_height() as f32 / (GRID_HEIGHT - 1) as f32).min(1.)
+    }
+
+    /// raise only while the expected stack is low and nothing is about to land
+    pub fn should_raise(&self) -> bool {
+        !self.garbage_falling && self.opponent_chain == 0 && self.expected_height() < SAFE_HEIGHT
+    }
+
+    /// allowed height difference between columns before peaks get flattened, shrinks with danger
+    pub fn peak_tolerance(&self) -> i32 {
+        let range = (CALM_PEAK_TOLERANCE - DANGER_PEAK_TOLERANCE) as f32;
+        CALM_PEAK_TOLERANCE - (range * self.danger()).round() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// an empty grid raises, garbage and opponent chains stop raising and lower the peak tolerance
+    #[test]
+    fn threat_evaluate() {
+        let mut garbage_system = GarbageSystem::default();
+        let mut grid = Grid::empty();
+
+        let calm = Threat::evaluate(&grid, &garbage_system);
+        assert_eq!(calm.expected_height(), 0);
+        assert!(calm.should_raise());
+        assert_eq!(calm.peak_tolerance(), CALM_PEAK_TOLERANCE);
+
+        for y in GRID_HEIGHT - 4..GRID_HEIGHT - 1 {
+            grid[y * GRID_WIDTH] = Component::spawn(Some(3));
+        }
+
+        grid.spawn_garbage(&mut garbage_system, i2(0, 0), GRID_WIDTH, 2);
+        grid.opponent_chain = 3;
+
+        let threat = Threat::evaluate(&grid, &garbage_system);
+        assert_eq!(threat.stack_height, 3);
+        assert_eq!(threat.garbage_rows, 2);
+        assert!(!threat.should_raise());
+        assert!(threat.peak_tolerance() < CALM_PEAK_TOLERANCE);
+
+        // garbage below the stack only counts once through the stack height
+        let mut buried = Grid::empty();
+        let mut garbage_system = GarbageSystem::default();
+        buried.spawn_garbage(
+            &mut garbage_system,
+            i2(0, GRID_HEIGHT as i32 - 3),
+            GRID_WIDTH,
+            2,
+        );
+        buried[(GRID_HEIGHT - 4) * GRID_WIDTH] = Component::spawn(Some(3));
+
+        let threat = Threat::evaluate(&buried, &garbage_system);
+        assert_eq!(threat.stack_height, 3);
+        assert_eq!(threat.garbage_rows, 0);
+        assert_eq!(threat.expected_height(), 3);
+    }
+}
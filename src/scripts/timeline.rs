@@ -0,0 +1,156 @@
+use crate::scripts::{GarbageSystem, Grid, ModeProgress};
+use std::collections::VecDeque;
+
+/// amount of seconds that can be rewinded
+const REWIND_SECONDS: usize = 10;
+/// frames the game runs per second
+const FRAMES_PER_SECOND: usize = 60;
+
+/// full copy of all grids, garbage and mode progress at the end of a frame
+#[derive(Clone)]
+pub struct Snapshot {
+    pub grids: Vec<Grid>,
+    pub garbage_system: GarbageSystem,
+    pub progress: Option<ModeProgress>,
+}
+
+/// ring buffer of the last frame snapshots that can be scrubbed through while paused
+pub struct Timeline {
+    snapshots: VecDeque<Snapshot>,
+
+    /// amount of frames the shown snapshot lies behind the newest one
+    pub rewinded: usize,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(REWIND_SECONDS * FRAMES_PER_SECOND),
+            rewinded: 0,
+        }
+    }
+}
+
+impl Timeline {
+    /// stores a copy of the frame, drops the oldest one once full
+    /// pushing after a rewind drops the frames that came after the shown one
+    pub fn push(
+        &mut self,
+        grids: &[Grid],
+        garbage_system: &GarbageSystem,
+        progress: Option<ModeProgress>,
+    ) {
+        let len = self.snapshots.len();
+        self.snapshots.truncate(len - self.rewinded);
+        self.rewinded = 0;
+
+        if self.snapshots.len() == REWIND_SECONDS * FRAMES_PER_SECOND {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(Snapshot {
+            grids: grids.to_vec(),
+            garbage_system: garbage_system.clone(),
+            progress,
+        });
+    }
+
+    /// moves one frame back, returns the snapshot to show if there was an older one
+    pub fn back(&mut self) -> Option<&Snapshot> {
+        if self.rewinded + 1 < self.snapshots.len() {
+            self.rewinded += 1;
+            self.current()
+        } else {
+            None
+        }
+    }
+
+    /// moves one frame forward, returns the snapshot to show if the timeline was rewinded
+    pub fn forward(&mut self) -> Option<&Snapshot> {
+        if self.rewinded > 0 {
+            self.rewinded -= 1;
+            self.current()
+        } else {
+            None
+        }
+    }
+
+    /// currently shown snapshot
+    pub fn current(&self) -> Option<&Snapshot> {
+        let len = self.snapshots.len();
+        self.snapshots.get(len.checked_sub(self.rewinded + 1)?)
+    }
+
+    /// removes all snapshots
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.rewinded = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripts::{LineClear, Mode};
+
+    fn grid_with_id(id: usize) -> Vec<Grid> {
+        let mut grid = Grid::empty();
+        grid.id = id;
+        vec![grid]
+    }
+
+    /// scrubbing back and forth shows the right frames, pushing after a rewind drops the future
+    #[test]
+    fn timeline_scrub() {
+        let mut timeline = Timeline::default();
+        let garbage_system = GarbageSystem::default();
+
+        for id in 0..3 {
+            timeline.push(&grid_with_id(id), &garbage_system, None);
+        }
+
+        assert!(timeline.forward().is_none());
+        assert_eq!(timeline.back().unwrap().grids[0].id, 1);
+        assert_eq!(timeline.back().unwrap().grids[0].id, 0);
+        assert!(timeline.back().is_none());
+        assert_eq!(timeline.forward().unwrap().grids[0].id, 1);
+
+        timeline.push(&grid_with_id(5), &garbage_system, None);
+        assert_eq!(timeline.current().unwrap().grids[0].id, 5);
+        assert_eq!(timeline.back().unwrap().grids[0].id, 1);
+    }
+
+    /// only the last seconds are kept
+    #[test]
+    fn timeline_capacity() {
+        let mut timeline = Timeline::default();
+        let garbage_system = GarbageSystem::default();
+
+        for id in 0..REWIND_SECONDS * FRAMES_PER_SECOND + 10 {
+            timeline.push(&grid_with_id(id), &garbage_system, None);
+        }
+
+        while timeline.back().is_some() {}
+        assert_eq!(timeline.current().unwrap().grids[0].id, 10);
+    }
+
+    /// single player progress is rewinded together with the grids
+    #[test]
+    fn timeline_mode_progress() {
+        let mut timeline = Timeline::default();
+        let garbage_system = GarbageSystem::default();
+        let mut mode = Mode::LineClear(LineClear::new(10, 120));
+
+        for counter in 0..3 {
+            if let Mode::LineClear(line_clear) = &mut mode {
+                line_clear.counter = counter;
+            }
+
+            timeline.push(&grid_with_id(0), &garbage_system, mode.progress());
+        }
+
+        let progress = timeline.back().unwrap().progress.unwrap();
+        mode.set_progress(progress);
+        assert!(matches!(&mode, Mode::LineClear(line_clear) if line_clear.counter == 1));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::engine::Input;
+use crate::helpers::*;
+use crate::scripts::{
+    ai_from_name, is_ai_name, update_versus, Ai, Difficulty, GarbageSystem, Grid,
+};
+use std::panic::AssertUnwindSafe;
+
+/// frames a match may last till it counts as a draw, 5 minutes
+pub const MATCH_FRAME_LIMIT: u32 = 60 * 60 * 5;
+/// frames the game runs per second, used to print the match duration
+const FRAMES_PER_SECOND: f32 = 60.;
+
+/// ai configuration that takes part in a tournament, written as "search:hard" or "heuristic"
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contestant {
+    pub ai: String,
+    pub difficulty: Difficulty,
+}
+
+impl Contestant {
+    /// parses "AI" or "AI:DIFFICULTY"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = text.split(':');
+        let ai = parts.next().unwrap_or("").to_string();
+
+        if !is_ai_name(&ai) {
+            return Err(format!("unknown ai {:?}", ai));
+        }
+
+        let difficulty = match parts.next() {
+            Some(name) => Difficulty::from_name(name)
+                .ok_or_else(|| format!("unknown difficulty {:?}", name))?,
+            None => Difficulty::default(),
+        };
+
+        Ok(Self { ai, difficulty })
+    }
+
+    /// name shown in the summary table
+    pub fn name(&self) -> String {
+        format!("{}:{}", self.ai, self.difficulty.name)
+    }
+
+    /// hands the grid over to a new ai of this configuration
+    pub fn control(&self, grid: &mut Grid) {
+        let ai: Option<Box<dyn Ai>> = ai_from_name(&self.ai);
+        grid.set_ai(ai);
+        grid.cursor.difficulty = self.difficulty;
+    }
+}
+
+/// outcome of a single match
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchResult {
+    /// index of the winning side, none on a draw
+    pub winner: Option<usize>,
+
+    pub frames: u32,
+    pub chains: [Vec<u32>; 2],
+    pub garbage_sent: [u32; 2],
+}
+
+/// both grids of a seeded match, starting with the same field
+pub fn match_grids(seed: u64) -> Vec<Grid> {
+    let vframes = {
+        let mut temp_random = oorandom::Rand32::new(seed);
+        Grid::gen_field(&mut temp_random, 5)
+    };
+
+    vec![
+        Grid::new(0, seed * 2 + 1, &vframes),
+        Grid::new(1, seed * 2 + 2, &vframes),
+    ]
+}
+
+/// plays a seeded cpu versus cpu match without a window till one side tops out
+pub fn play_match(left: &Contestant, right: &Contestant, seed: u64) -> MatchResult {
+    let mut grids = match_grids(seed);
+    left.control(&mut grids[0]);
+    right.control(&mut grids[1]);
+
+    let input = Input::headless();
+    let mut garbage_system = GarbageSystem::default();
+    let mut frames = 0;
+    let mut winner = None;
+
+    while frames < MATCH_FRAME_LIMIT {
+        update_versus(&input, &mut grids, &mut garbage_system);
+        frames += 1;
+
+        match (grids[0].topped_out, grids[1].topped_out) {
+            (false, false) => continue,
+            (true, false) => winner = Some(1),
+            (false, true) => winner = Some(0),
+            (true, true) => {}
+        }
+
+        break;
+    }
+
+    MatchResult {
+        winner,
+        frames,
+        chains: [grids[0].chains.clone(), grids[1].chains.clone()],
+        garbage_sent: [grids[0].garbage_sent, grids[1].garbage_sent],
+    }
+}
+
+/// summed up results of a contestant over all its matches
+#[derive(Default)]
+struct Stats {
+    matches: u32,
+    skipped: u32,
+    wins: u32,
+    draws: u32,
+    chains: u32,
+    chain_links: u32,
+    garbage_sent: u32,
+    frames: u32,
+}
+
+impl Stats {
+    /// adds the result of the side the contestant played on
+    fn add(&mut self, result: &MatchResult, side: usize) {
+        self.matches += 1;
+        self.frames += result.frames;
+        self.garbage_sent += result.garbage_sent[side];
+        self.chains += result.chains[side].len() as u32;
+        self.chain_links += result.chains[side].iter().sum::<u32>();
+
+        match result.winner {
+            Some(winner) if winner == side => self.wins += 1,
+            None => self.draws += 1,
+            _ => {}
+        }
+    }
+}
+
+/// runs the tournament from the command line arguments "MATCHES CONTESTANT..." and prints a summary table
+/// every pair of contestants plays MATCHES seeded matches, the sides switch each match
+pub fn run_tournament(args: &[String]) {
+    let matches = args
+        .first()
+        .and_then(|arg| arg.parse::<u64>().ok())
+        .unwrap_or(100);
+
+    let names: Vec<&str> = if args.len() > 1 {
+        args[1..].iter().map(String::as_str).collect()
+    } else {
+        vec!["heuristic", "search"]
+    };
+
+    let mut contestants = Vec::new();
+    for name in names {
+        match Contestant::parse(name) {
+            Ok(contestant) => contestants.push(contestant),
+
+            Err(e) => {
+                eprintln!("TOURNAMENT: {}", e);
+                return;
+            }
+        }
+    }
+
+    if contestants.len() < 2 {
+        eprintln!("TOURNAMENT: needs at least 2 contestants");
+        return;
+    }
+
+    let mut stats: Vec<Stats> = contestants.iter().map(|_| Stats::default()).collect();
+
+    for a in 0..contestants.len() {
+        for b in a + 1..contestants.len() {
+            for seed in 0..matches {
+                // switch sides so neither contestant profits from the field generation
+                let (left, right) = if seed % 2 == 0 { (a, b) } else { (b, a) };
+
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    play_match(&contestants[left], &contestants[right], seed)
+                }));
+
+                match result {
+                    Ok(result) => {
+                        stats[left].add(&result, 0);
+                        stats[right].add(&result, 1);
+                    }
+
+                    // NOTE(Skytrias): skipped matches are listed in the summary since they are missing in the win rate
+                    Err(_) => {
+                        stats[left].skipped += 1;
+                        stats[right].skipped += 1;
+
+                        eprintln!(
+                            "TOURNAMENT: match {} vs {} with seed {} panicked, skipped",
+                            contestants[left].name(),
+                            contestants[right].name(),
+                            seed
+                        );
+                    }
+                }
+            }
+
+            eprintln!(
+                "TOURNAMENT: {} vs {} done",
+                contestants[a].name(),
+                contestants[b].name()
+            );
+        }
+    }
+
+    println!(
+        "{:<20} {:>8} {:>8} {:>8} {:>8} {:>10} {:>10} {:>10}",
+        "ai", "matches", "skipped", "win %", "draws", "avg chain", "garbage", "avg secs"
+    );
+
+    for (contestant, stats) in contestants.iter().zip(stats.iter()) {
+        let matches = stats.matches.max(1) as f32;
+
+        println!(
+            "{:<20} {:>8} {:>8} {:>8.1} {:>8} {:>10.2} {:>10.1} {:>10.1}",
+            contestant.name(),
+            stats.matches,
+            stats.skipped,
+            stats.wins as f32 / matches * 100.,
+            stats.draws,
+            stats.chain_links as f32 / stats.chains.max(1) as f32,
+            stats.garbage_sent as f32 / matches,
+            stats.frames as f32 / matches / FRAMES_PER_SECOND,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// contestants are parsed with an optional difficulty
+    #[test]
+    fn contestant_parse() {
+        let contestant = Contestant::parse("search:hard").unwrap();
+        assert_eq!(contestant.ai, "search");
+        assert_eq!(contestant.difficulty.name, "hard");
+        assert_eq!(
+            Contestant::parse("heuristic").unwrap().difficulty,
+            Difficulty::default()
+        );
+        assert!(Contestant::parse("nobody").is_err());
+        assert!(Contestant::parse("bot=not-started").is_ok());
+        assert!(Contestant::parse("bot=").is_err());
+        assert!(Contestant::parse("search:impossible").is_err());
+    }
+
+    /// the same seed plays out the same match
+    #[test]
+    fn match_deterministic() {
+        let contestant = Contestant::parse("heuristic:expert").unwrap();
+        let first = play_match(&contestant, &contestant, 7);
+        assert!(first.frames <= MATCH_FRAME_LIMIT);
+        assert_eq!(first, play_match(&contestant, &contestant, 7));
+    }
+}